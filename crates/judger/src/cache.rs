@@ -0,0 +1,146 @@
+//! 评测过程中的文件系统缓存（例如编译结果）
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use std::{fs, time::SystemTime};
+use store::{FsStore, Handle};
+
+/// 基于 SHA-256 的 [`std::hash::Hasher`]，计算结果不依赖于 Rust 版本和进程
+///
+/// 注意 [`std::hash::Hash`] 对整数（例如字符串长度）的写入使用本机字节序，
+/// 因此同一个缓存目录不应在不同架构的机器之间共享。
+#[derive(Default, Clone)]
+pub struct ContentHasher(Sha256);
+
+impl ContentHasher {
+    /// 以十六进制表示的摘要
+    pub fn hex_digest(self) -> String {
+        hex::encode(self.0.finalize())
+    }
+}
+
+impl std::hash::Hasher for ContentHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+}
+
+/// 保存在文件夹中的 LRU 缓存，可以被多个评测进程共享
+///
+/// 每个条目是缓存目录下以 key 命名的文件（夹），其修改时间即为最近一次访问的时间。
+/// 写入时先保存到临时目录再重命名，因此其他进程不会读到写了一半的条目。
+pub struct Cache {
+    root: Handle,
+    capacity: usize,
+}
+
+impl Cache {
+    /// 最多保存 `capacity` 个条目
+    pub fn new(root: Handle, capacity: usize) -> Self {
+        Self { root, capacity }
+    }
+    pub fn root(&self) -> &Handle {
+        &self.root
+    }
+    /// 读取缓存并更新其访问时间。条目不存在或者无法读取时返回 None
+    pub fn get<R: FsStore>(&self, key: &str) -> Option<R> {
+        let path = self.root.join(key);
+        let r = R::open(&path).ok()?;
+        // 条目可能恰好被其他进程淘汰，此时忽略错误
+        let _ = fs::File::open(path.path()).and_then(|f| f.set_modified(SystemTime::now()));
+        Some(r)
+    }
+    /// 写入缓存。若 key 已经存在（例如其他进程已经写入）则保留已有的条目
+    pub fn insert<R: FsStore>(&self, key: &str, value: &mut R) -> anyhow::Result<()> {
+        let path = self.root.join(key);
+        if path.path().exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(self.root.path()).context("create cache dir")?;
+        let tmp = tempfile::Builder::new()
+            .prefix(".tmp")
+            .tempdir_in(self.root.path())
+            .context("create tmp dir in cache dir")?;
+        let tmpdest = Handle::new(tmp.path().join("entry"));
+        value.save(&tmpdest).context("save cache entry")?;
+        if fs::rename(tmpdest.path(), path.path()).is_err() && !path.path().exists() {
+            anyhow::bail!("fail to move cache entry to {}", path.path().display())
+        }
+        self.evict()
+    }
+    /// 按照访问时间淘汰多余的条目
+    fn evict(&self) -> anyhow::Result<()> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(self.root.path()).context("read cache dir")? {
+            let entry = entry?;
+            // 跳过临时文件夹
+            if entry.file_name().as_encoded_bytes().starts_with(b".") {
+                continue;
+            }
+            let Ok(mtime) = entry.metadata().and_then(|m| m.modified()) else {
+                continue;
+            };
+            entries.push((mtime, entry.file_name()));
+        }
+        if entries.len() <= self.capacity {
+            return Ok(());
+        }
+        entries.sort();
+        let n = entries.len() - self.capacity;
+        for (_, name) in entries.into_iter().take(n) {
+            // 先移出缓存目录，保证其他进程看不到删除了一半的条目
+            let Ok(trash) = tempfile::Builder::new()
+                .prefix(".evict")
+                .tempdir_in(self.root.path())
+            else {
+                continue;
+            };
+            let _ = fs::rename(self.root.join(&name).path(), trash.path().join("entry"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::Hasher;
+
+    #[test]
+    fn test_content_hash() {
+        let mut h = ContentHasher::default();
+        h.write(b"abc");
+        assert_eq!(
+            h.hex_digest(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_lru() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = Cache::new(Handle::new(dir.path()), 2);
+        for key in ["a", "b"] {
+            cache.insert(key, &mut key.to_string())?;
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        // a 比 b 更近被访问
+        assert_eq!(cache.get::<String>("a").as_deref(), Some("a"));
+        cache.insert("c", &mut "c".to_string())?;
+        assert_eq!(cache.get::<String>("a").as_deref(), Some("a"));
+        assert!(cache.get::<String>("b").is_none());
+        assert_eq!(cache.get::<String>("c").as_deref(), Some("c"));
+
+        // 重复写入不会覆盖已有的条目
+        cache.insert("c", &mut "d".to_string())?;
+        assert_eq!(cache.get::<String>("c").as_deref(), Some("c"));
+
+        let names = fs::read_dir(dir.path())?.count();
+        assert_eq!(names, 2, "temporary dirs should be removed");
+        Ok(())
+    }
+}
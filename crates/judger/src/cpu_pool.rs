@@ -0,0 +1,71 @@
+use sandbox::unix::SingletonConfig;
+use std::sync::{Condvar, Mutex};
+
+/// A set of CPUs shared by concurrent judgers, so that each running program
+/// is pinned to a core that no other program is using at the same time.
+///
+/// Pinned programs also have address space randomization disabled,
+/// see [`SingletonConfig::cpus`] and [`SingletonConfig::disable_aslr`].
+pub struct CpuPool {
+    idle: Mutex<Vec<u32>>,
+    released: Condvar,
+}
+
+impl CpuPool {
+    pub fn new(cpus: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            idle: Mutex::new(cpus.into_iter().collect()),
+            released: Condvar::new(),
+        }
+    }
+    /// Take an idle CPU, blocking until one is released if all are in use.
+    pub fn acquire(&self) -> CpuLease<'_> {
+        let mut idle = self.idle.lock().expect("cpu pool lock poisoned");
+        loop {
+            if let Some(cpu) = idle.pop() {
+                return CpuLease { pool: self, cpu };
+            }
+            idle = self.released.wait(idle).expect("cpu pool lock poisoned");
+        }
+    }
+    /// Pin `cfg` to an idle CPU, which is released when the returned lease is dropped.
+    pub fn pin(&self, cfg: SingletonConfig) -> (SingletonConfig, CpuLease<'_>) {
+        let lease = self.acquire();
+        (cfg.cpus([lease.cpu]).disable_aslr(), lease)
+    }
+}
+
+/// A CPU taken from [`CpuPool`], returned to the pool on drop.
+pub struct CpuLease<'a> {
+    pool: &'a CpuPool,
+    cpu: u32,
+}
+
+impl CpuLease<'_> {
+    pub fn cpu(&self) -> u32 {
+        self.cpu
+    }
+}
+
+impl Drop for CpuLease<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut idle) = self.pool.idle.lock() {
+            idle.push(self.cpu);
+            self.pool.released.notify_one();
+        }
+    }
+}
+
+/// pin `cfg` with `pool` if there is one
+pub(crate) fn pin(
+    pool: Option<&CpuPool>,
+    cfg: SingletonConfig,
+) -> (SingletonConfig, Option<CpuLease<'_>>) {
+    match pool {
+        Some(pool) => {
+            let (cfg, lease) = pool.pin(cfg);
+            (cfg, Some(lease))
+        }
+        None => (cfg, None),
+    }
+}
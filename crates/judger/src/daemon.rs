@@ -0,0 +1,85 @@
+use crate::{cpu_pool, CpuPool, Judger};
+use anyhow::Context;
+use sandbox::{unix::SingletonConfig, Termination};
+use std::{
+    io::{BufRead, BufReader, Write},
+    marker::PhantomData,
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// A judger that executes programs with a running `zroj-sandbox serve` daemon,
+/// instead of spawning a `zroj-sandbox` process for each execution.
+///
+/// The connection is kept and reused. Since the daemon resolves relative paths
+/// against its own working directory, the working directory should be absolute.
+pub struct DaemonJudger<M> {
+    wd: store::Handle,
+    socket: PathBuf,
+    conn: Mutex<Option<BufReader<UnixStream>>>,
+    cpu_pool: Option<Arc<CpuPool>>,
+    _mark: PhantomData<M>,
+}
+
+impl<M> DaemonJudger<M> {
+    /// `socket` is the path passed to `zroj-sandbox serve --socket`
+    pub fn new(wd: store::Handle, socket: impl Into<PathBuf>) -> Self {
+        Self {
+            wd,
+            socket: socket.into(),
+            conn: Mutex::new(None),
+            cpu_pool: None,
+            _mark: PhantomData,
+        }
+    }
+    /// pin the executed programs to the CPUs of `pool`
+    pub fn with_cpu_pool(mut self, pool: Arc<CpuPool>) -> Self {
+        self.cpu_pool = Some(pool);
+        self
+    }
+}
+
+fn request(conn: &mut BufReader<UnixStream>, line: &str) -> anyhow::Result<String> {
+    conn.get_mut().write_all(line.as_bytes())?;
+    let mut resp = String::new();
+    if conn.read_line(&mut resp)? == 0 {
+        anyhow::bail!("connection closed by sandbox daemon");
+    }
+    Ok(resp)
+}
+
+impl<M: std::fmt::Display> Judger<M> for DaemonJudger<M> {
+    fn working_dir(&self) -> &store::Handle {
+        &self.wd
+    }
+    fn runtime_log(&mut self, msg: M) {
+        eprintln!("[judger] {}", msg)
+    }
+    fn cpu_pool(&self) -> Option<&CpuPool> {
+        self.cpu_pool.as_deref()
+    }
+    fn exec_sandbox(&self, cfg: SingletonConfig) -> anyhow::Result<Termination> {
+        let (cfg, _lease) = cpu_pool::pin(self.cpu_pool(), cfg);
+        let mut line = serde_json::to_string(&cfg).context("serialize sandbox config")?;
+        line.push('\n');
+
+        let mut conn = self.conn.lock().expect("connection lock poisoned");
+        // the kept connection may be closed if the daemon restarted, thus retry once
+        let resp = match conn.as_mut().map(|c| request(c, &line)) {
+            Some(Ok(resp)) => Ok(resp),
+            _ => UnixStream::connect(&self.socket)
+                .with_context(|| format!("connect to sandbox daemon {:?}", self.socket))
+                .map(BufReader::new)
+                .and_then(|c| request(conn.insert(c), &line)),
+        };
+        let resp = resp
+            .inspect_err(|_| *conn = None)
+            .context("request sandbox daemon")?;
+        drop(conn);
+
+        let term: Result<Termination, Vec<String>> =
+            serde_json::from_str(&resp).context("deserialize sandbox output")?;
+        term.map_err(|e| anyhow::anyhow!("sandbox error: {e:?}"))
+    }
+}
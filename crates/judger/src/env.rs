@@ -11,10 +11,9 @@ pub fn os_family() -> &'static str {
         "windows"
     }
 }
-/// 注意，我们希望 judger 执行的命令都是我们已知的命令，
-/// 而不考虑用户自定义命令（不安全），因此使用 string literal
-/// 作为参数。此外命令名字里不应当包含路径（/或者\）
-pub fn which(cmd_name: &'static str) -> anyhow::Result<String> {
+/// 注意，我们希望 judger 执行的命令都是我们已知的命令（string literal 或者语言配置中的命令），
+/// 而不考虑用户自定义命令（不安全）。此外命令名字里不应当包含路径（/或者\）
+pub fn which(cmd_name: &str) -> anyhow::Result<String> {
     let os_cmd_name = std::ffi::OsString::from(cmd_name);
     if let Ok(path) = std::env::var("PATH") {
         if cfg!(all(unix)) {
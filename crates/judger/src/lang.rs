@@ -1,27 +1,17 @@
-use sandbox::unix::{Lim, Limitation, SingletonConfig};
+use anyhow::{anyhow, bail, Context as _};
+use sandbox::unix::{EnvPolicy, Lim, Limitation, SingletonConfig, SyscallPolicy};
 use sandbox::{Elapse, Memory};
 use serde::{Deserialize, Serialize};
-use serde_ts_typing::TsType;
+use serde_ts_typing::{Context, TsType, TypeExpr};
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
 use store::Handle;
 
-/// 使用 g++ 编译 C++ 源文件
-pub struct GnuCpp {
-    gpp_path: String,
-    extra_args: Vec<String>,
-}
-
-impl GnuCpp {
-    /// 默认编译器为 g++
-    pub fn new(gpp_path: Option<String>, args: Vec<&'static str>) -> Self {
-        let gpp_path = gpp_path.unwrap_or(crate::env::which("g++").unwrap());
-        // dbg!(&gpp_path);
-        let extra_args: Vec<String> = args.into_iter().map(|s| s.to_string()).collect();
-        GnuCpp {
-            gpp_path,
-            extra_args,
-        }
-    }
-}
+/// 编译器需要从评测进程继承的环境变量（用于查找汇编器、链接器以及临时目录）
+const COMPILE_ENVS: &[&str] = &["PATH", "TMPDIR"];
 
 /// General resource limitation config for compilers.
 pub const COMPILE_LIM: Limitation = Limitation {
@@ -32,82 +22,490 @@ pub const COMPILE_LIM: Limitation = Limitation {
     stack_memory: Lim::Single(Memory::from_mb(4096)),
     output_memory: Lim::Single(Memory::from_mb(1024)),
     fileno: Lim::Single(200),
+    nproc: Lim::Single(1024),
+    instructions: None,
 };
 
-impl GnuCpp {
-    fn compile_sandbox(&self, source: &Handle, dest: &Handle, log: &Handle) -> SingletonConfig {
-        SingletonConfig::new(&self.gpp_path)
-            .push_args(["g++"])
-            .push_args(self.extra_args.iter().map(|s| s.as_str()))
-            .push_args([&source.to_string(), "-o", &dest.to_string()])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM)
-            .stderr(log.to_string())
+/// 内置的语言配置，在没有加载配置文件时使用
+const BUILTIN_LANGUAGES: &str = include_str!("languages.json");
+
+/// 一门语言（文件类型）的配置
+///
+/// 评测分为准备（编译）和运行两步：准备步骤将源文件转换为产物，运行步骤执行该产物。
+/// 对于编译型语言，产物就是可执行文件；对于解释型语言，准备步骤通常是语法检查或者编译为字节码，
+/// 运行步骤则通过解释器执行产物（例如 `["python3", "{exec}"]`）。
+/// 内置的 Python 配置使用 `-S`（不导入 site 模块，它查询用户信息时会连接 socket）和
+/// `-B`（导入模块时不写入字节码缓存），从而可以在系统调用白名单下运行。
+///
+/// 指令模板的第一项为程序名（不含 `/` 时在 `PATH` 中查找），其余为参数。
+/// 模板中的 `{source}`、`{dest}` 和 `{exec}` 分别会被替换为源文件、编译产物和可执行文件的路径。
+/// 单独作为一项参数的 `{sources}` 会被展开为所有参与编译的源文件（见 [`LinkStrategy`]）。
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Language {
+    /// 语言的标识，即 [`FileType`] 的取值
+    pub id: String,
+    /// 展示给用户的名称
+    pub name: String,
+    /// 源文件的后缀名
+    pub ext: String,
+    /// 编译（准备）指令模板，为空表示该语言的文件不能被执行。
+    /// 不需要准备的解释型语言可以使用 `["cp", "{source}", "{dest}"]`
+    #[serde(default)]
+    pub compile: Option<Vec<String>>,
+    /// 多文件编译时如何处理题目提供的附加文件
+    #[serde(default)]
+    pub link: LinkStrategy,
+    /// 编译为导出 C ABI 函数的目标文件的方式，为空表示不支持（例如不能用于编写 C ABI checker）
+    #[serde(default)]
+    pub c_abi: Option<CAbi>,
+    /// 运行指令模板
+    #[serde(default = "default_run")]
+    pub run: Vec<String>,
+    /// 编译时额外继承的环境变量（`PATH` 和 `TMPDIR` 总是会被继承）
+    #[serde(default)]
+    pub compile_envs: Vec<String>,
+    /// 运行时继承的环境变量，例如解释器用于查找标准库的变量。默认不继承任何环境变量
+    #[serde(default)]
+    pub run_envs: Vec<String>,
+    /// 编译时的资源限制
+    #[serde(default = "default_compile_limit")]
+    pub compile_limit: Limitation,
+    /// 运行时允许使用的系统调用，默认为 [`SyscallPolicy::native`]
+    #[serde(default = "SyscallPolicy::native")]
+    pub syscall_policy: SyscallPolicy,
+    /// 在 `syscall_policy` 的基础上额外允许的系统调用，例如解释器导入模块时需要的 `getdents64`
+    #[serde(default)]
+    pub allow_syscalls: Vec<String>,
+    /// 时间限制的倍率
+    #[serde(default = "default_ratio")]
+    pub time_ratio: f64,
+    /// 内存限制的倍率
+    #[serde(default = "default_ratio")]
+    pub memory_ratio: f64,
+}
+
+/// 编译单元（见 [`crate::CompileUnit`]）中附加文件的链接方式
+///
+/// 无论采用哪种方式，附加文件都会被写入工作目录，因此总是可以被源文件引用（例如 `#include "grader.h"`、
+/// Rust 的 `mod grader;` 或者 Python 的 `import grader`）。
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkStrategy {
+    /// 只将选手的源文件传给编译器，适用于由编译器或解释器自行查找模块的语言
+    #[default]
+    MainOnly,
+    /// 将文件类型与选手的源文件相同的附加文件一并传给编译器（`{sources}`），
+    /// 例如 C/C++ 的 grader 源文件。头文件等其他类型的文件不会被传入
+    SameType,
+}
+
+/// 将源文件编译为导出 C ABI 函数的目标文件（或静态库），再由 C 编译器与其他目标文件链接
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CAbi {
+    /// 指令模板，`{source}` 和 `{dest}` 分别为源文件和目标文件的路径
+    pub compile: Vec<String>,
+    /// 链接时需要的额外参数，例如该语言的运行时库
+    #[serde(default)]
+    pub link_args: Vec<String>,
+}
+
+fn default_run() -> Vec<String> {
+    vec!["{exec}".into()]
+}
+fn default_compile_limit() -> Limitation {
+    COMPILE_LIM
+}
+fn default_ratio() -> f64 {
+    1.0
+}
+
+/// 程序名不含 `/` 时在 `PATH` 中查找
+fn resolve_program(prog: &str) -> anyhow::Result<String> {
+    if prog.contains('/') {
+        Ok(prog.to_string())
+    } else {
+        crate::which(prog)
+    }
+}
+
+/// 将指令模板展开为 [`SingletonConfig`]，argv\[0\] 为程序的文件名
+///
+/// `vars` 替换参数中出现的变量；恰好等于 `lists` 中某个变量的参数会被展开为多项参数
+fn expand_template(
+    template: &[String],
+    vars: &[(&str, &str)],
+    lists: &[(&str, &[String])],
+) -> anyhow::Result<SingletonConfig> {
+    let args: Vec<String> = template
+        .iter()
+        .flat_map(|arg| match lists.iter().find(|(k, _)| k == arg) {
+            Some((_, list)) => list.to_vec(),
+            None => vec![vars
+                .iter()
+                .fold(arg.clone(), |arg, (k, v)| arg.replace(k, v))],
+        })
+        .collect();
+    let (prog, rest) = args.split_first().context("empty command template")?;
+    let path = resolve_program(prog)?;
+    let argv0 = Path::new(prog)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(prog);
+    Ok(SingletonConfig::new(path)
+        .push_args([argv0])
+        .push_args(rest.iter().map(|s| s.as_str())))
+}
+
+impl Language {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.id.is_empty() {
+            bail!("empty language id");
+        }
+        if self.compile.as_ref().is_some_and(|c| c.is_empty())
+            || self.c_abi.as_ref().is_some_and(|c| c.compile.is_empty())
+            || self.run.is_empty()
+        {
+            bail!("empty command template for language {}", self.id);
+        }
+        if self.link == LinkStrategy::SameType
+            && !self
+                .compile
+                .as_ref()
+                .is_some_and(|c| c.iter().any(|a| a == "{sources}"))
+        {
+            bail!(
+                "language {} links extra files but never uses {{sources}}",
+                self.id
+            );
+        }
+        if !(self.time_ratio > 0.0 && self.memory_ratio > 0.0) {
+            bail!("non-positive limit ratio for language {}", self.id);
+        }
+        Ok(())
+    }
+}
+
+/// 评测端支持的语言列表，通常从配置文件中加载
+///
+/// 配置文件是一个由 [`Language`] 组成的 JSON 数组，格式参考内置配置 `languages.json`。
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct LanguageRegistry {
+    languages: Vec<Language>,
+}
+
+static REGISTRY: OnceLock<LanguageRegistry> = OnceLock::new();
+
+impl LanguageRegistry {
+    /// 检查语言配置是否合法（标识不重复、指令模板非空等）
+    pub fn new(languages: Vec<Language>) -> anyhow::Result<Self> {
+        for (i, lang) in languages.iter().enumerate() {
+            lang.validate()?;
+            if languages[..i].iter().any(|o| o.id == lang.id) {
+                bail!("duplicated language id {}", lang.id);
+            }
+        }
+        Ok(Self { languages })
+    }
+    /// 内置的语言配置
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_LANGUAGES).expect("invalid builtin languages")
+    }
+    /// 从 JSON 字符串中解析语言配置
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Self::new(serde_json::from_str(json).context("parse language config")?)
+    }
+    /// 从 JSON 文件中加载语言配置
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path).context("read language config")?;
+        Self::from_json(&json)
     }
+    /// 根据标识查找语言
+    pub fn get(&self, id: &str) -> Option<&Language> {
+        self.languages.iter().find(|o| o.id == id)
+    }
+    /// 所有语言，按照配置中的顺序
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+    /// 设置全局的语言列表。必须在第一次使用 [`FileType`] 的方法之前调用，否则会使用内置配置
+    pub fn install(self) -> anyhow::Result<()> {
+        REGISTRY
+            .set(self)
+            .map_err(|_| anyhow!("language registry has been initialized"))
+    }
+    /// 全局的语言列表
+    pub fn global() -> &'static Self {
+        REGISTRY.get_or_init(Self::builtin)
+    }
+}
+
+/// 文件类型，即 [`LanguageRegistry`] 中语言的标识
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileType(Cow<'static, str>);
+
+/// 内置语言配置（见 `languages.json`）中的语言标识
+impl FileType {
+    pub const GNU_CPP20_O2: FileType = FileType(Cow::Borrowed("gnu_cpp20_o2"));
+    pub const GNU_CPP17_O2: FileType = FileType(Cow::Borrowed("gnu_cpp17_o2"));
+    pub const GNU_CPP14_O2: FileType = FileType(Cow::Borrowed("gnu_cpp14_o2"));
+    pub const PLAIN: FileType = FileType(Cow::Borrowed("plain"));
+    pub const PYTHON3: FileType = FileType(Cow::Borrowed("python3"));
+    pub const RUST: FileType = FileType(Cow::Borrowed("rust"));
+    pub const GNU_ASSEMBLY: FileType = FileType(Cow::Borrowed("gnu_assembly"));
 }
 
-/// 内置的支持的文件类型
-#[derive(Serialize, Deserialize, Clone, Debug, TsType, PartialEq, Eq, Hash)]
-#[non_exhaustive]
-pub enum FileType {
-    #[serde(rename = "gnu_cpp20_o2")]
-    GnuCpp20O2,
-    #[serde(rename = "gnu_cpp17_o2")]
-    GnuCpp17O2,
-    #[serde(rename = "gnu_cpp14_o2")]
-    GnuCpp14O2,
-    #[serde(rename = "plain")]
-    Plain,
-    #[serde(rename = "python3")]
-    Python,
-    #[serde(rename = "rust")]
-    Rust,
-    #[serde(rename = "gnu_assembly")]
-    Assembly,
+impl TsType for FileType {
+    fn register_context(c: &mut Context) {
+        c.register(
+            TypeId::of::<Self>(),
+            "FileType".into(),
+            TypeExpr::String,
+            " 文件类型，即评测端语言配置中的语言标识\n".into(),
+        );
+    }
+    fn type_def() -> TypeExpr {
+        TypeExpr::Ident(TypeId::of::<Self>(), "FileType".into())
+    }
 }
 
 impl FileType {
-    /// 获取文件类型对应的后缀名
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(Cow::Owned(id.into()))
+    }
+    /// 语言的标识
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    /// 在全局的语言列表中查找对应的语言
+    pub fn language(&self) -> anyhow::Result<&'static Language> {
+        LanguageRegistry::global()
+            .get(&self.0)
+            .with_context(|| format!("unknown language {}", self.0))
+    }
+    /// 获取文件类型对应的后缀名，未知的类型视为纯文本
     pub fn ext(&self) -> &'static str {
-        match &self {
-            FileType::GnuCpp20O2 | FileType::GnuCpp17O2 | FileType::GnuCpp14O2 => "cpp",
-            FileType::Plain => "txt",
-            FileType::Python => "py",
-            FileType::Rust => "rs",
-            FileType::Assembly => "s",
-        }
+        self.language().map(|l| l.ext.as_str()).unwrap_or("txt")
     }
     pub fn compileable(&self) -> bool {
-        !matches!(self, FileType::Plain)
+        self.language().is_ok_and(|l| l.compile.is_some())
+    }
+    /// 多文件编译时，类型为 `other` 的附加文件是否和该语言的源文件一起传给编译器
+    pub fn links(&self, other: &FileType) -> bool {
+        self.language()
+            .is_ok_and(|l| l.link == LinkStrategy::SameType && self == other)
+    }
+    /// 编译该语言时所需的环境变量，其余的环境变量不会传给编译器
+    pub fn compile_env_policy(&self) -> EnvPolicy {
+        let policy = EnvPolicy::default().inherit(COMPILE_ENVS.iter().copied());
+        match self.language() {
+            Ok(lang) => policy.inherit(lang.compile_envs.iter().map(|s| s.as_str())),
+            Err(_) => policy,
+        }
+    }
+    /// 运行该语言生成的可执行文件时允许使用的系统调用
+    pub fn exec_syscall_policy(&self) -> SyscallPolicy {
+        match self.language() {
+            Ok(lang) => lang
+                .syscall_policy
+                .clone()
+                .allow(lang.allow_syscalls.iter().map(|s| s.as_str())),
+            Err(_) => SyscallPolicy::native(),
+        }
+    }
+    /// 工具链的指纹，由语言配置以及编译器、解释器的路径、大小和修改时间组成。
+    /// 编译器升级后指纹会发生变化，用于区分不同版本工具链的编译结果
+    pub fn toolchain_fingerprint(&self) -> String {
+        let Ok(lang) = self.language() else {
+            return format!("unknown:{}", self.0);
+        };
+        let mut r = serde_json::to_string(lang).unwrap_or_default();
+        let progs = lang
+            .compile
+            .iter()
+            .chain([&lang.run])
+            .filter_map(|t| t.first());
+        for prog in progs.filter(|p| !p.contains('{')) {
+            let Ok(path) = resolve_program(prog) else {
+                continue;
+            };
+            if let Ok(meta) = std::fs::metadata(&path) {
+                let mtime = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .unwrap_or_default();
+                r += &format!("\n{path}:{}:{}", meta.len(), mtime.as_nanos());
+            }
+        }
+        r
+    }
+    /// 按照该语言的倍率放缩时间限制
+    pub fn scale_time(&self, time: Elapse) -> Elapse {
+        match self.language() {
+            Ok(lang) => time * lang.time_ratio,
+            Err(_) => time,
+        }
+    }
+    /// 按照该语言的倍率放缩内存限制
+    pub fn scale_memory(&self, memory: Memory) -> Memory {
+        match self.language() {
+            Ok(lang) => memory * lang.memory_ratio,
+            Err(_) => memory,
+        }
     }
 }
 
 impl FileType {
     /// 生成一个编译指令，将源文件编译为可执行文件
     ///
-    /// - source: 源文件路径
+    /// - sources: 参与编译的源文件路径，第一项为选手的源文件（`{source}`），
+    ///   其余为按照 [`FileType::links`] 筛选出的附加文件
     /// - dest: 编译产生的可执行文件的路径
     /// - log: 编译日志文件
-    pub fn compile_sandbox(&self, source: &Handle, dest: &Handle, log: &Handle) -> SingletonConfig {
-        match self {
-            FileType::GnuCpp20O2 => {
-                GnuCpp::new(None, vec!["-std=c++2a", "-O2", "-Wall", "-Wextra"])
-                    .compile_sandbox(source, dest, log)
-            }
-            FileType::GnuCpp17O2 => {
-                GnuCpp::new(None, vec!["-std=c++17", "-O2", "-Wall", "-Wextra"])
-                    .compile_sandbox(source, dest, log)
-            }
-            FileType::GnuCpp14O2 => {
-                GnuCpp::new(None, vec!["-std=c++14", "-O2", "-Wall", "-Wextra"])
-                    .compile_sandbox(source, dest, log)
-            }
-            FileType::Plain => panic!("a plain file should never be compiled"),
-            FileType::Rust => SingletonConfig::new(crate::which("rustc").unwrap())
-                .push_args(["rustc", &source.to_string(), "-o", &dest.to_string()])
-                .with_current_env()
-                .set_limits(|_| COMPILE_LIM),
-            _ => unimplemented!(),
+    pub fn compile_sandbox(
+        &self,
+        sources: &[Handle],
+        dest: &Handle,
+        log: &Handle,
+    ) -> anyhow::Result<SingletonConfig> {
+        let lang = self.language()?;
+        let template = lang
+            .compile
+            .as_ref()
+            .with_context(|| format!("language {} should never be compiled", lang.id))?;
+        let source = sources.first().context("no source file to compile")?;
+        let sources: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
+        let cfg = expand_template(
+            template,
+            &[
+                ("{source}", &source.to_string()),
+                ("{dest}", &dest.to_string()),
+            ],
+            &[("{sources}", &sources)],
+        )?;
+        let limit = lang.compile_limit.clone();
+        Ok(cfg
+            .set_limits(|_| limit)
+            .stderr(log.to_string())
+            .env_policy(self.compile_env_policy()))
+    }
+    /// 生成一个编译指令，将源文件编译为导出 C ABI 函数的目标文件，同时返回链接时需要的参数
+    pub fn c_abi_sandbox(
+        &self,
+        source: &Handle,
+        dest: &Handle,
+        log: &Handle,
+    ) -> anyhow::Result<(SingletonConfig, &'static [String])> {
+        let lang = self.language()?;
+        let c_abi = lang
+            .c_abi
+            .as_ref()
+            .with_context(|| format!("language {} does not support C ABI", lang.id))?;
+        let cfg = expand_template(
+            &c_abi.compile,
+            &[
+                ("{source}", &source.to_string()),
+                ("{dest}", &dest.to_string()),
+            ],
+            &[],
+        )?;
+        let limit = lang.compile_limit.clone();
+        let cfg = cfg
+            .set_limits(|_| limit)
+            .stderr(log.to_string())
+            .env_policy(self.compile_env_policy());
+        Ok((cfg, &c_abi.link_args))
+    }
+    /// 生成一个运行指令，运行编译产生的可执行文件（或者通过解释器运行编译产物）。资源限制需要另外设置
+    pub fn exec_sandbox(&self, exec: &Handle) -> anyhow::Result<SingletonConfig> {
+        let lang = self.language()?;
+        let cfg = expand_template(&lang.run, &[("{exec}", &exec.to_string())], &[])?;
+        Ok(cfg
+            .syscall_policy(self.exec_syscall_policy())
+            .env_policy(EnvPolicy::default().inherit(lang.run_envs.iter().map(|s| s.as_str()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_registry() {
+        let reg = LanguageRegistry::builtin();
+        for ft in [
+            FileType::GNU_CPP20_O2,
+            FileType::GNU_CPP17_O2,
+            FileType::GNU_CPP14_O2,
+            FileType::PLAIN,
+            FileType::PYTHON3,
+            FileType::RUST,
+            FileType::GNU_ASSEMBLY,
+        ] {
+            assert!(reg.get(ft.as_str()).is_some(), "{ft:?}");
         }
+        assert_eq!(FileType::GNU_CPP17_O2.ext(), "cpp");
+        assert!(!FileType::PLAIN.compileable());
+        assert!(!FileType::new("unknown").compileable());
+        assert_eq!(
+            FileType::GNU_CPP17_O2.toolchain_fingerprint(),
+            FileType::GNU_CPP17_O2.toolchain_fingerprint()
+        );
+        assert_ne!(
+            FileType::GNU_CPP17_O2.toolchain_fingerprint(),
+            FileType::GNU_CPP14_O2.toolchain_fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_parse_registry() {
+        let reg = LanguageRegistry::from_json(
+            r#"[{
+                "id": "gnu_c11",
+                "name": "C11",
+                "ext": "c",
+                "compile": ["gcc", "-std=c11", "{source}", "-o", "{dest}"],
+                "time_ratio": 1.5
+            }]"#,
+        )
+        .unwrap();
+        let lang = reg.get("gnu_c11").unwrap();
+        assert_eq!(lang.run, ["{exec}"]);
+        assert_eq!(lang.time_ratio, 1.5);
+        assert_eq!(lang.memory_ratio, 1.0);
+        assert_eq!(lang.syscall_policy, SyscallPolicy::native());
+
+        let ft: FileType = serde_json::from_str(r#""gnu_c11""#).unwrap();
+        assert_eq!(ft, FileType::new("gnu_c11"));
+        assert_eq!(serde_json::to_string(&FileType::RUST).unwrap(), r#""rust""#);
+
+        let dup = r#"[{"id": "a", "name": "A", "ext": "a"}, {"id": "a", "name": "B", "ext": "b"}]"#;
+        assert!(LanguageRegistry::from_json(dup).is_err());
+
+        let no_sources = r#"[{"id": "a", "name": "A", "ext": "a", "compile": ["cc", "{source}"], "link": "same_type"}]"#;
+        assert!(LanguageRegistry::from_json(no_sources).is_err());
+    }
+
+    #[test]
+    fn test_expand_sources() -> anyhow::Result<()> {
+        assert!(FileType::GNU_CPP17_O2.links(&FileType::GNU_CPP17_O2));
+        assert!(!FileType::GNU_CPP17_O2.links(&FileType::PLAIN));
+        assert!(!FileType::RUST.links(&FileType::RUST));
+
+        let sources = [Handle::new("/tmp/main.cpp"), Handle::new("/tmp/grader.cpp")];
+        let cfg = FileType::GNU_CPP17_O2.compile_sandbox(
+            &sources,
+            &Handle::new("/tmp/main"),
+            &Handle::new("/tmp/main.clog"),
+        )?;
+        let args = serde_json::to_value(&cfg)?["arguments"].clone();
+        let args: Vec<String> = serde_json::from_value(args)?;
+        let pos = args.iter().position(|a| a == "/tmp/main.cpp").unwrap();
+        assert_eq!(args[pos + 1], "/tmp/grader.cpp");
+        assert!(FileType::GNU_CPP17_O2
+            .compile_sandbox(&[], &sources[0], &sources[1])
+            .is_err());
+        Ok(())
     }
 }
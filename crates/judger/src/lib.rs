@@ -1,26 +1,35 @@
 //! ZROJ 的评测模块
 #![allow(dead_code)]
 
+mod cache;
+mod cpu_pool;
+#[cfg(unix)]
+mod daemon;
 mod env;
 mod lang;
 mod one_off;
 mod report;
 mod store_file;
 pub mod truncstr;
+mod unit;
 
-use std::{hash::Hash, marker::PhantomData, process::Stdio};
+use std::{hash::Hash, marker::PhantomData, process::Stdio, sync::Arc};
 
-use anyhow::Context;
-// pub use cache::Cache;
 use ::sandbox::{unix::SingletonConfig, Termination};
+use anyhow::Context;
+pub use cache::{Cache, ContentHasher};
+pub use cpu_pool::{CpuLease, CpuPool};
+#[cfg(unix)]
+pub use daemon::DaemonJudger;
 pub use env::which;
-pub use lang::{FileType, COMPILE_LIM};
+pub use lang::{CAbi, FileType, Language, LanguageRegistry, LinkStrategy, COMPILE_LIM};
 pub use one_off::OneOff;
 pub use report::*;
 use store::FsStore;
 pub use store::Handle;
 pub use store_file::{SourceFile, StoreFile};
 use truncstr::TruncStr;
+pub use unit::CompileUnit;
 
 /// Loosen the constraint of [`std::hash::Hash`],
 /// for [`std::fs::File`] associated hash.
@@ -37,6 +46,24 @@ impl HashMut for StoreFile {
     }
 }
 
+/// 除了源代码以外还包含工具链的指纹，因此升级编译器后不会使用旧的编译结果
+impl HashMut for SourceFile {
+    fn hash_mut<H: std::hash::Hasher>(&mut self, state: &mut H) -> anyhow::Result<()> {
+        let fingerprint = self.file_type.toolchain_fingerprint();
+        for part in [&self.source, self.file_type.as_str(), &fingerprint] {
+            state.write(&(part.len() as u64).to_le_bytes());
+            state.write(part.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl HashMut for &mut SourceFile {
+    fn hash_mut<H: std::hash::Hasher>(&mut self, state: &mut H) -> anyhow::Result<()> {
+        (**self).hash_mut(state)
+    }
+}
+
 impl<T: Hash> HashMut for T {
     fn hash_mut<H: std::hash::Hasher>(&mut self, state: &mut H) -> anyhow::Result<()> {
         Hash::hash(&self, state);
@@ -76,15 +103,34 @@ pub trait Judger<M: std::fmt::Display> {
     /// If you're applying [`Judger::cachable_block`], you should use [`Compilation::execfile`] for
     /// further processing.
     fn compile(&self, file: &mut SourceFile, name: &str) -> anyhow::Result<Compilation> {
+        self.compile_unit(&mut CompileUnit::new(file), name)
+    }
+
+    /// Same as [`Judger::compile`], but also writes the extra files of `unit` to
+    /// `working_dir/{file name}`. Extra files are passed to the compiler
+    /// according to the [`LinkStrategy`] of the language.
+    ///
+    /// You need to ensure these files do not exist either.
+    fn compile_unit(&self, unit: &mut CompileUnit, name: &str) -> anyhow::Result<Compilation> {
+        unit.check_names()?;
         let wd = self.working_dir();
-        let src = wd.join(name).with_extension(file.file_type.ext());
+        let file_type = unit.main.file_type.clone();
+        let src = wd.join(name).with_extension(file_type.ext());
         let exec = wd.join(name);
         let clog = wd.join(name).with_extension("clog");
 
-        file.copy_all(&mut src.create_new_file()?)?;
+        unit.main.copy_all(&mut src.create_new_file()?)?;
+        let mut sources = vec![src];
+        for (fname, file) in &mut unit.extra {
+            let dest = wd.join(fname);
+            file.copy_all(&mut dest.create_new_file()?)?;
+            if file_type.links(&file.file_type) {
+                sources.push(dest);
+            }
+        }
 
         let term = self
-            .exec_sandbox(file.file_type.compile_sandbox(&src, &exec, &clog))
+            .exec_sandbox(file_type.compile_sandbox(&sources, &exec, &clog)?)
             .context("compile file")?;
         Ok(Compilation {
             termination: term,
@@ -139,8 +185,14 @@ pub trait Judger<M: std::fmt::Display> {
         func(self, inputs)
     }
 
+    /// The CPUs to pin the executed programs to, shared with other judgers running concurrently.
+    fn cpu_pool(&self) -> Option<&CpuPool> {
+        None
+    }
+
     /// Use the `zroj-sandbox` to execute
     fn exec_sandbox(&self, cfg: SingletonConfig) -> anyhow::Result<Termination> {
+        let (cfg, _lease) = cpu_pool::pin(self.cpu_pool(), cfg);
         let mut child = std::process::Command::new("zroj-sandbox")
             .arg("run")
             .stdin(Stdio::piped())
@@ -160,20 +212,38 @@ pub trait Judger<M: std::fmt::Display> {
     }
 }
 
+/// Default capacity of the compile cache of [`DefaultJudger`].
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
 /// A simple judger that prints logs to `stderr`.
 pub struct DefaultJudger<M> {
     wd: store::Handle,
-    cached: Option<store::Handle>,
+    cache: Option<Cache>,
+    cpu_pool: Option<Arc<CpuPool>>,
     _mark: PhantomData<M>,
 }
 impl<M> DefaultJudger<M> {
+    /// `cached` is the cache directory, which can be shared by multiple judgers.
     pub fn new(wd: store::Handle, cached: Option<store::Handle>) -> Self {
         Self {
             wd,
-            cached,
+            cache: cached.map(|root| Cache::new(root, DEFAULT_CACHE_CAPACITY)),
+            cpu_pool: None,
             _mark: PhantomData,
         }
     }
+    /// keep at most `capacity` entries in the cache directory, evicting the least recently used ones
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        if let Some(cache) = &mut self.cache {
+            *cache = Cache::new(cache.root().clone(), capacity);
+        }
+        self
+    }
+    /// pin the executed programs to the CPUs of `pool`
+    pub fn with_cpu_pool(mut self, pool: Arc<CpuPool>) -> Self {
+        self.cpu_pool = Some(pool);
+        self
+    }
 }
 impl<M: std::fmt::Display> Judger<M> for DefaultJudger<M> {
     fn working_dir(&self) -> &store::Handle {
@@ -182,35 +252,29 @@ impl<M: std::fmt::Display> Judger<M> for DefaultJudger<M> {
     fn runtime_log(&mut self, msg: M) {
         eprintln!("[judger] {}", msg)
     }
-    /// implement a simple fs cache
+    fn cpu_pool(&self) -> Option<&CpuPool> {
+        self.cpu_pool.as_deref()
+    }
+    /// implement a LRU fs cache keyed by the SHA-256 digest of inputs
     fn cachable_block<I: HashMut, R: FsStore>(
         &self,
         func: impl FnOnce(&Self, I) -> anyhow::Result<R>,
         mut inputs: I,
     ) -> anyhow::Result<R> {
-        if let Some(cache_root) = &self.cached {
-            let h = {
-                let mut s = std::hash::DefaultHasher::new();
+        if let Some(cache) = &self.cache {
+            let key = {
+                let mut s = ContentHasher::default();
                 inputs.hash_mut(&mut s).context("calcuate input hash")?;
-                std::hash::Hasher::finish(&s)
+                s.hex_digest()
             };
-            let path = cache_root.join(h.to_string());
-            if path.path().exists() {
-                if let Ok(r) = R::open(&path) {
-                    eprintln!("[judger] find cache {:?}", path.path());
-                    return Ok(r);
-                }
-                eprintln!(
-                    "[judger] find cache but fail to deserialize {:?}",
-                    path.path()
-                );
-            } else {
-                eprintln!("[judger] not find cache {:?}", path.path());
+            if let Some(r) = cache.get(&key) {
+                eprintln!("[judger] find cache {key}");
+                return Ok(r);
             }
+            eprintln!("[judger] not find cache {key}");
             let mut r = func(self, inputs)?;
-            r.save(&path)?;
-            assert!(path.path().exists());
-            eprintln!("[judger] add cache {:?}", path.path());
+            cache.insert(&key, &mut r).context("add cache")?;
+            eprintln!("[judger] add cache {key}");
             Ok(r)
         } else {
             func(self, inputs)
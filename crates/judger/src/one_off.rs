@@ -3,7 +3,7 @@
 use crate::{Judger, SourceFile, StoreFile, TaskMeta, TaskReport};
 use anyhow::Context;
 use sandbox::{
-    unix::{Lim, Limitation, SingletonConfig},
+    unix::{Lim, Limitation},
     Elapse, Memory,
 };
 use store::Handle;
@@ -22,6 +22,7 @@ pub struct OneOff {
     memory_limit: Memory,
     output_limit: Memory,
     fileno_limit: u64,
+    nproc_limit: u64,
 }
 
 impl OneOff {
@@ -35,6 +36,7 @@ impl OneOff {
             memory_limit: Memory::from_mb(1024),
             output_limit: Memory::from_mb(128),
             fileno_limit: 10,
+            nproc_limit: 1,
         }
     }
     pub fn set_wd(&mut self, dir: Handle) -> &mut Self {
@@ -76,24 +78,29 @@ impl OneOff {
         let output = judger.clear_dest("output")?;
         let log = judger.clear_dest("log")?;
 
-        let s = SingletonConfig::new(exec.to_string())
-            .push_args(["main"])
+        let file_type = &self.file.file_type;
+        let time_limit = file_type.scale_time(self.time_limit);
+        let memory_limit = file_type.scale_memory(self.memory_limit);
+        let s = file_type
+            .exec_sandbox(&exec)?
             .stdin(input.to_string())
             .stdout(output.to_string())
             .stderr(log.to_string())
             .set_limits(|_| Limitation {
-                real_time: Lim::Double(self.time_limit, Elapse::from(self.time_limit.ms() * 2)),
-                cpu_time: self.time_limit.into(),
-                virtual_memory: self.memory_limit.into(),
-                real_memory: self.memory_limit.into(),
-                stack_memory: self.memory_limit.into(),
+                real_time: Lim::Double(time_limit, Elapse::from(time_limit.ms() * 2)),
+                cpu_time: time_limit.into(),
+                virtual_memory: memory_limit.into(),
+                real_memory: memory_limit.into(),
+                stack_memory: memory_limit.into(),
                 output_memory: self.output_limit.into(),
                 fileno: self.fileno_limit.into(),
+                nproc: self.nproc_limit.into(),
+                instructions: None,
             });
 
         let term = judger.exec_sandbox(s)?;
 
-        let status: crate::Status = term.status.into();
+        let status = crate::Status::from(&term);
         let mut report = TaskReport::new(TaskMeta {
             score_rate: status.direct_score_rate(),
             status,
@@ -125,9 +132,9 @@ int main() {
     for(;;) cout << "h" << endl;
 }
 "#,
-            FileType::GnuCpp17O2,
+            FileType::GNU_CPP17_O2,
         );
-        let input = StoreFile::from_str(r"1 2", FileType::Plain);
+        let input = StoreFile::from_str(r"1 2", FileType::PLAIN);
         let mut oneoff = OneOff::new(source, input);
         let dir = tempfile::TempDir::new().unwrap();
         oneoff.set_wd(Handle::new(dir.path()));
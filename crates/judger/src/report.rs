@@ -19,16 +19,21 @@ pub enum Status {
     /// 编译错误
     CompileError(Option<sandbox::Status>),
 
-    // DangerousSyscall,
+    /// 调用了被禁止的系统调用
+    DangerousSyscall,
     /// 超出内存限制
     MemoryLimitExceeded,
-    // OutputLimitExceeded,
+    /// 输出文件大小超出限制
+    OutputLimitExceeded,
     // (获得的部分分，总分）
     // Partial(f64, f64),
     // 非空字符构成的字符串与答案匹配
     // PresentationError,
-    RuntimeError,
+    /// 运行时错误，附带原因（例如 `SIGSEGV`、`exit code 3`）
+    RuntimeError(Option<String>),
     TimeLimitExceeded,
+    /// 程序长时间没有使用 CPU（例如等待读入），被提前终止
+    IdlenessLimitExceeded,
     // WrongAnswer,
 }
 
@@ -96,7 +101,7 @@ impl std::fmt::Debug for TaskReport {
             if alternate {
                 let srcfile = crate::SourceFile {
                     source: content.to_string(),
-                    file_type: crate::FileType::Plain,
+                    file_type: crate::FileType::PLAIN,
                 };
                 builder.field(name, &srcfile);
             } else {
@@ -155,10 +160,32 @@ impl From<sandbox::Status> for Status {
     fn from(value: sandbox::Status) -> Self {
         match value {
             sandbox::Status::Ok => Status::Good,
-            sandbox::Status::RuntimeError(_) => Status::RuntimeError,
+            sandbox::Status::RuntimeError(_) => Status::RuntimeError(None),
+            sandbox::Status::ProcessLimitExceeded(_) => {
+                Status::RuntimeError(Some("process limit exceeded".into()))
+            }
             sandbox::Status::MemoryLimitExceeded => Status::MemoryLimitExceeded,
             sandbox::Status::TimeLimitExceeded => Status::TimeLimitExceeded,
-            _ => unimplemented!(),
+            sandbox::Status::OutputLimitExceeded => Status::OutputLimitExceeded,
+            sandbox::Status::DangerousSyscall(_) => Status::DangerousSyscall,
+            sandbox::Status::IdlenessLimitExceeded => Status::IdlenessLimitExceeded,
+            // sandbox::Status 是 non_exhaustive 的，未知的状态视为运行时错误
+            other => Status::RuntimeError(Some(format!("{other:?}"))),
+        }
+    }
+}
+
+/// 与 `From<sandbox::Status>` 相同，但运行时错误会附带终止程序的信号或返回值
+impl From<&sandbox::Termination> for Status {
+    fn from(term: &sandbox::Termination) -> Self {
+        match term.status {
+            sandbox::Status::RuntimeError(_) => Status::RuntimeError(
+                term.signal
+                    .as_ref()
+                    .map(|s| s.to_string())
+                    .or_else(|| term.exit_code.map(|c| format!("exit code {c}"))),
+            ),
+            _ => term.status.clone().into(),
         }
     }
 }
@@ -10,7 +10,7 @@ use std::{
 use store::FsStore;
 
 /// 一个带类型的 buffer
-#[derive(Serialize, Deserialize, TsType, Clone, Hash)]
+#[derive(Serialize, Deserialize, TsType, Clone)]
 pub struct SourceFile {
     pub source: String,
     pub file_type: FileType,
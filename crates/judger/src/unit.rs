@@ -0,0 +1,64 @@
+use crate::{HashMut, SourceFile, StoreFile};
+use std::path::{Component, Path};
+
+/// 编译单元：选手的源文件以及题目提供的附加文件（例如交互式题目的 grader 和头文件）
+///
+/// 附加文件会以给定的文件名写入工作目录，并按照语言的 [`crate::LinkStrategy`]
+/// 决定是否一起传给编译器。
+pub struct CompileUnit<'a> {
+    pub main: &'a mut SourceFile,
+    /// 附加文件的文件名及其内容
+    pub extra: Vec<(&'a str, &'a mut StoreFile)>,
+}
+
+impl<'a> CompileUnit<'a> {
+    pub fn new(main: &'a mut SourceFile) -> Self {
+        Self {
+            main,
+            extra: Vec::new(),
+        }
+    }
+    /// 添加一个附加文件，`name` 必须是不含路径的文件名
+    pub fn with_file(mut self, name: &'a str, file: &'a mut StoreFile) -> Self {
+        self.extra.push((name, file));
+        self
+    }
+    /// 检查附加文件的文件名，防止写到工作目录之外
+    pub(crate) fn check_names(&self) -> anyhow::Result<()> {
+        for (name, _) in &self.extra {
+            let mut comps = Path::new(name).components();
+            anyhow::ensure!(
+                matches!(
+                    (comps.next(), comps.next()),
+                    (Some(Component::Normal(_)), None)
+                ),
+                "invalid file name {name:?} in compile unit"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl HashMut for CompileUnit<'_> {
+    fn hash_mut<H: std::hash::Hasher>(&mut self, state: &mut H) -> anyhow::Result<()> {
+        self.main.hash_mut(state)?;
+        for (name, file) in &mut self.extra {
+            let content = file.read_to_bytes()?;
+            for part in [
+                name.as_bytes(),
+                file.file_type.as_str().as_bytes(),
+                &content,
+            ] {
+                state.write(&(part.len() as u64).to_le_bytes());
+                state.write(part);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HashMut for &mut CompileUnit<'_> {
+    fn hash_mut<H: std::hash::Hasher>(&mut self, state: &mut H) -> anyhow::Result<()> {
+        (**self).hash_mut(state)
+    }
+}
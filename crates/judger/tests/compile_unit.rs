@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod compile_unit {
+    use judger::{
+        CompileUnit, DefaultJudger, FileType, Judger, SourceFile, StoreFile, COMPILE_LIM,
+    };
+    use store::Handle;
+
+    /// 编译单元并以 `input` 为标准输入运行，返回标准输出
+    fn compile_and_run(
+        judger: &DefaultJudger<&str>,
+        unit: &mut CompileUnit,
+        input: &str,
+    ) -> anyhow::Result<String> {
+        let file_type = unit.main.file_type.clone();
+        let judger::Compilation {
+            termination,
+            log_payload,
+            execfile,
+        } = judger.compile_unit(unit, "main")?;
+        assert!(termination.status.ok(), "{}", String::from(&log_payload));
+        assert!(execfile.is_some());
+
+        let stdin = judger.create_source_file(input, "input")?;
+        let stdout = judger.clear_dest("output")?;
+        let term = judger.exec_sandbox(
+            file_type
+                .exec_sandbox(&judger.working_dir().join("main"))?
+                .stdin(stdin.to_string())
+                .stdout(stdout.to_string())
+                .set_limits(|_| COMPILE_LIM),
+        )?;
+        assert!(term.status.ok(), "{term:?}");
+        Ok(std::fs::read_to_string(stdout.path())?)
+    }
+
+    #[test]
+    fn test_cpp_grader() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let judger = DefaultJudger::<&str>::new(Handle::new(dir.path()), None);
+
+        let mut main = SourceFile::from_str(
+            "#include \"grader.h\"\nint add(int a, int b) { return a + b; }\n",
+            FileType::GNU_CPP17_O2,
+        );
+        let mut header = StoreFile::from_str("int add(int a, int b);\n", FileType::PLAIN);
+        let mut grader = StoreFile::from_str(
+            r#"
+#include <cstdio>
+#include "grader.h"
+int main() {
+    int a, b;
+    scanf("%d%d", &a, &b);
+    printf("%d\n", add(a, b));
+}
+"#,
+            FileType::GNU_CPP17_O2,
+        );
+        let mut unit = CompileUnit::new(&mut main)
+            .with_file("grader.h", &mut header)
+            .with_file("grader.cpp", &mut grader);
+
+        assert_eq!(compile_and_run(&judger, &mut unit, "1 2")?, "3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_module() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let judger = DefaultJudger::<&str>::new(Handle::new(dir.path()), None);
+
+        let mut main = SourceFile::from_str(
+            "import helper\nprint(helper.add(*map(int, input().split())))\n",
+            FileType::PYTHON3,
+        );
+        let mut helper =
+            StoreFile::from_str("def add(a, b):\n    return a + b\n", FileType::PYTHON3);
+        let mut unit = CompileUnit::new(&mut main).with_file("helper.py", &mut helper);
+
+        assert_eq!(compile_and_run(&judger, &mut unit, "1 2")?, "3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let judger = DefaultJudger::<&str>::new(Handle::new(dir.path()), None);
+
+        let mut main = SourceFile::from_str("int main() {}", FileType::GNU_CPP17_O2);
+        let mut extra = StoreFile::from_str("", FileType::PLAIN);
+        let mut unit = CompileUnit::new(&mut main).with_file("../grader.h", &mut extra);
+        assert!(judger.compile_unit(&mut unit, "main").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(unix)]
+mod daemon {
+    use judger::{DaemonJudger, Judger};
+    use sandbox::{unix::SingletonConfig, Status};
+    use store::Handle;
+
+    #[test]
+    fn test_daemon_judger() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("sandbox.sock");
+        let mut daemon = std::process::Command::new("zroj-sandbox")
+            .arg("serve")
+            .arg("--socket")
+            .arg(&socket)
+            .spawn()?;
+        while !socket.exists() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let ls_path = judger::which("ls")?;
+        let sleep_path = judger::which("sleep")?;
+        let r = std::thread::scope(|s| {
+            // requests from different connections are executed concurrently
+            let h = s.spawn(|| {
+                let judger = DaemonJudger::<&str>::new(Handle::new(&dir), &socket);
+                judger.exec_sandbox(SingletonConfig::new(&sleep_path).push_args(["sleep", "1"]))
+            });
+            let judger = DaemonJudger::<&str>::new(Handle::new(&dir), &socket);
+            let terms = (0..3)
+                .map(|_| judger.exec_sandbox(SingletonConfig::new(&ls_path).push_args(["ls"])))
+                .collect::<anyhow::Result<Vec<_>>>();
+            (h.join().unwrap(), terms)
+        });
+        daemon.kill()?;
+        daemon.wait()?;
+
+        let (sleep_term, ls_terms) = r;
+        assert_eq!(sleep_term?.status, Status::Ok);
+        for term in ls_terms? {
+            assert_eq!(term.status, Status::Ok);
+            assert!(term.real_time.ms() < 1000);
+        }
+        Ok(())
+    }
+}
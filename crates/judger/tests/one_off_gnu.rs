@@ -21,8 +21,8 @@ int main() {
 
         let dir = tempfile::tempdir().unwrap();
 
-        let src = SourceFile::from_str(a_plus_b_raw, FileType::GnuCpp17O2);
-        let inp = StoreFile::from_str(input_content, FileType::Plain);
+        let src = SourceFile::from_str(a_plus_b_raw, FileType::GNU_CPP17_O2);
+        let inp = StoreFile::from_str(input_content, FileType::PLAIN);
 
         let mut one = OneOff::new(src, inp);
         one.set_wd(Handle::new(&dir));
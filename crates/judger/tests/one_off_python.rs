@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod one_off {
+    use judger::{FileType, OneOff, SourceFile, Status, StoreFile};
+    use store::Handle;
+
+    #[test]
+    fn test_python() -> anyhow::Result<()> {
+        let a_plus_b_raw = r#"
+a, b = map(int, input().split())
+print(a + b)
+"#;
+        let dir = tempfile::tempdir().unwrap();
+
+        let src = SourceFile::from_str(a_plus_b_raw, FileType::PYTHON3);
+        let inp = StoreFile::from_str("1 2", FileType::PLAIN);
+
+        let mut one = OneOff::new(src, inp);
+        one.set_wd(Handle::new(&dir));
+
+        let res = one.exec()?;
+        if let Status::Good = res.meta.status {
+            assert_eq!(String::from(&res.payload[0].1), "3\n");
+        } else {
+            panic!("not accepted, res = {:?}", res)
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_syntax_error() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+
+        let src = SourceFile::from_str("print(1 +", FileType::PYTHON3);
+        let inp = StoreFile::from_str("", FileType::PLAIN);
+
+        let mut one = OneOff::new(src, inp);
+        one.set_wd(Handle::new(&dir));
+
+        let res = one.exec()?;
+        assert!(
+            matches!(res.meta.status, Status::CompileError(_)),
+            "res = {res:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_socket() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+
+        let src = SourceFile::from_str("import socket\nsocket.socket()\n", FileType::PYTHON3);
+        let inp = StoreFile::from_str("", FileType::PLAIN);
+
+        let mut one = OneOff::new(src, inp);
+        one.set_wd(Handle::new(&dir));
+
+        let res = one.exec()?;
+        assert!(
+            matches!(res.meta.status, Status::DangerousSyscall),
+            "res = {res:?}"
+        );
+        Ok(())
+    }
+}
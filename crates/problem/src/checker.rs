@@ -124,6 +124,37 @@ fn auto_cmp(
 }
 
 impl Checker {
+    /// 编译 checker（如果需要的话），返回 checker 可执行文件的路径。内置的 checker 返回 None
+    ///
+    /// 编译得到的 checker 可以在多个测试点之间复用，见 [`Checker::check_prepared`]
+    pub fn prepare<M: std::fmt::Display>(
+        &mut self,
+        judger: &impl Judger<M>,
+    ) -> anyhow::Result<Option<Handle>> {
+        match self {
+            Checker::FileCmp | Checker::AutoCmp { .. } => Ok(None),
+            Checker::TestlibChecker {
+                testlib_header,
+                checker,
+            } => {
+                judger.copy_store_file(testlib_header, "testlib.h")?;
+                let judger::Compilation {
+                    termination,
+                    execfile,
+                    ..
+                } = judger.cachable_block(
+                    |judger, checker| judger.compile(checker, "checker-pre"),
+                    checker,
+                )?;
+
+                let mut execfile =
+                    execfile.with_context(|| format!("compile checker error: {termination:?}"))?;
+                Ok(Some(judger.copy_file(&mut execfile, "checker")?))
+            }
+            Checker::CABI { source } => Ok(Some(compile_cabi_checker(judger, source)?)),
+        }
+    }
+
     /// 检查正确性，返回正确与否和详细信息
     pub fn check<M: std::fmt::Display>(
         &mut self,
@@ -131,6 +162,19 @@ impl Checker {
         input: &Handle,
         output: &Handle,
         answer: &Handle,
+    ) -> anyhow::Result<(f64, String)> {
+        let exec = self.prepare(judger)?;
+        self.check_prepared(judger, exec.as_ref(), input, output, answer)
+    }
+
+    /// 使用 [`Checker::prepare`] 得到的 checker 检查正确性
+    pub fn check_prepared<M: std::fmt::Display>(
+        &self,
+        judger: &impl Judger<M>,
+        exec: Option<&Handle>,
+        input: &Handle,
+        output: &Handle,
+        answer: &Handle,
     ) -> anyhow::Result<(f64, String)> {
         let Ok(fout) = output.open_file() else {
             return Ok((0., "can not open output file".into()));
@@ -157,23 +201,8 @@ impl Checker {
                 Ok(msg) => Ok((1., msg)),
                 Err(msg) => Ok((0., msg)),
             },
-            Checker::TestlibChecker {
-                testlib_header,
-                checker,
-            } => {
-                judger.copy_store_file(testlib_header, "testlib.h")?;
-                let judger::Compilation {
-                    termination,
-                    execfile,
-                    ..
-                } = judger.cachable_block(
-                    |judger, checker| judger.compile(checker, "checker-pre"),
-                    checker,
-                )?;
-
-                let mut execfile =
-                    execfile.with_context(|| format!("compile checker error: {termination:?}"))?;
-                let checker = judger.copy_file(&mut execfile, "checker")?;
+            Checker::TestlibChecker { .. } => {
+                let checker = exec.context("checker is not prepared")?;
                 let checker_log = judger.clear_dest("checker.log")?;
 
                 let term = judger.exec_sandbox(
@@ -198,27 +227,14 @@ impl Checker {
                     t => Err(anyhow::anyhow!("checker error: {t:?}, {checker_log}")),
                 }
             }
-            Checker::CABI { source } => {
-                let exec = match source.file_type {
-                    judger::FileType::GnuCpp20O2 => {
-                        compile_cabi_checker_cpp(judger, source, "--std=c++2a")
-                    }
-                    judger::FileType::GnuCpp17O2 => {
-                        compile_cabi_checker_cpp(judger, source, "--std=c++17")
-                    }
-                    judger::FileType::GnuCpp14O2 => {
-                        compile_cabi_checker_cpp(judger, source, "--std=c++14")
-                    }
-                    judger::FileType::Rust => compile_cabi_checker_rust(judger, source),
-                    _ => unimplemented!(),
-                }?;
+            Checker::CABI { .. } => {
+                let exec = exec.context("checker is not prepared")?;
                 let checker_out = judger.clear_dest("checker_stdout")?;
 
                 // use default limitation
                 judger.exec_sandbox(
                     SingletonConfig::new(exec.to_string())
                         .push_args(["checker", &judger.working_dir().to_string()])
-                        .with_current_env()
                         .stdout(checker_out.to_string()),
                 )?;
 
@@ -236,124 +252,62 @@ impl Checker {
     }
 }
 
-fn compile_cabi_checker_cpp<M: std::fmt::Display>(
+/// 将 checker 编译为导出 `check` 函数的目标文件，再与 `checker_c_abi.c` 链接。
+/// 编译指令和链接参数由语言配置中的 [`judger::CAbi`] 给出
+fn compile_cabi_checker<M: std::fmt::Display>(
     judger: &impl Judger<M>,
     source: &mut SourceFile,
-    stdflag: &str,
 ) -> anyhow::Result<Handle> {
     judger.create_source_file(include_str!("./checker_c_abi.h"), "checker_c_abi.h")?;
     let c_abi_main =
         judger.create_source_file(include_str!("./checker_c_abi.c"), "checker_c_abi.c")?;
-    let cpp_impl_src = judger.create_source_file(&source.source, "checker.cpp")?;
-    let main_obj = judger.clear_dest("main.o")?;
-    let checker_obj = judger.clear_dest("checker.o")?;
-    let exec = judger.clear_dest("checker")?;
-
-    judger.exec_sandbox(
-        SingletonConfig::new(judger::which("cc").unwrap())
-            .push_args([
-                "cc",
-                &c_abi_main.to_string(),
-                "-o",
-                &main_obj.to_string(),
-                "-c",
-                "-O2",
-            ])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM),
-    )?;
-
-    judger.exec_sandbox(
-        SingletonConfig::new(judger::which("g++")?)
-            .push_args([
-                "g++",
-                &cpp_impl_src.to_string(),
-                "-o",
-                &checker_obj.to_string(),
-                "-c",
-                stdflag,
-                "-O2",
-            ])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM),
-    )?;
-
-    judger.exec_sandbox(
-        SingletonConfig::new(judger::which("cc")?)
-            .push_args([
-                "cc",
-                "-o",
-                &exec.to_string(),
-                &main_obj.to_string(),
-                &checker_obj.to_string(),
-                "-O2",
-                "-lstdc++",
-            ])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM),
+    let impl_src = judger.create_source_file(
+        &source.source,
+        &format!("checker.{}", source.file_type.ext()),
     )?;
-
-    assert!(exec.path().exists());
-
-    Ok(exec)
-}
-
-fn compile_cabi_checker_rust<M: std::fmt::Display>(
-    judger: &impl Judger<M>,
-    source: &mut SourceFile,
-) -> anyhow::Result<Handle> {
-    judger.create_source_file(include_str!("./checker_c_abi.h"), "checker_c_abi.h")?;
-    let c_abi_main =
-        judger.create_source_file(include_str!("./checker_c_abi.c"), "checker_c_abi.c")?;
-    let rust_impl_src = judger.create_source_file(&source.source, "checker.rs")?;
     let main_obj = judger.clear_dest("main.o")?;
-    let checker_lib = judger.clear_dest("libchecker.a")?;
+    let checker_obj = judger.clear_dest("checker.o")?;
     let exec = judger.clear_dest("checker")?;
-
-    judger.exec_sandbox(
-        SingletonConfig::new(judger::which("cc").unwrap())
-            .push_args([
-                "cc",
-                &c_abi_main.to_string(),
-                "-o",
-                &main_obj.to_string(),
-                "-c",
-                "-O2",
-            ])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM),
-    )?;
-
-    judger.exec_sandbox(
-        SingletonConfig::new(judger::which("rustc")?)
-            .push_args([
-                "rustc",
-                "--crate-type=staticlib",
-                &rust_impl_src.to_string(),
-                "-o",
-                &checker_lib.to_string(),
-            ])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM),
-    )?;
-
-    judger.exec_sandbox(
-        SingletonConfig::new(judger::which("cc")?)
-            .push_args([
-                "cc",
-                "-o",
-                &exec.to_string(),
-                &main_obj.to_string(),
-                &checker_lib.to_string(),
-                "-O2",
-                "-lpthread",
-                "-ldl",
-            ])
-            .with_current_env()
-            .set_limits(|_| COMPILE_LIM),
-    )?;
-
-    assert!(exec.path().exists());
+    let log = judger.clear_dest("checker.clog")?;
+
+    let (compile, link_args) = source
+        .file_type
+        .c_abi_sandbox(&impl_src, &checker_obj, &log)?;
+    let cc = judger::which("cc")?;
+    let cc = |args: &[&str]| {
+        SingletonConfig::new(&cc)
+            .push_args(args.iter().copied())
+            .env_policy(source.file_type.compile_env_policy())
+            .set_limits(|_| COMPILE_LIM)
+            .stderr(log.to_string())
+    };
+    let steps = [
+        cc(&[
+            "cc",
+            &c_abi_main.to_string(),
+            "-o",
+            &main_obj.to_string(),
+            "-c",
+            "-O2",
+        ]),
+        compile,
+        cc(&[
+            "cc",
+            "-o",
+            &exec.to_string(),
+            &main_obj.to_string(),
+            &checker_obj.to_string(),
+            "-O2",
+        ])
+        .push_args(link_args.iter().map(|s| s.as_str())),
+    ];
+    for cfg in steps {
+        let term = judger.exec_sandbox(cfg)?;
+        if !term.status.ok() {
+            let log = std::fs::read_to_string(&log).unwrap_or_default();
+            anyhow::bail!("compile checker error: {:?}, {log}", term.status);
+        }
+    }
 
     Ok(exec)
 }
@@ -367,22 +321,22 @@ mod tests {
     #[test]
     fn test_compare_byline() {
         compare_byline(
-            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::Plain).file),
-            BufReader::new(StoreFile::from_str("1 2\n3 4\n", judger::FileType::Plain).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::PLAIN).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4\n", judger::FileType::PLAIN).file),
             |_, _, _| Ok(()),
         )
         .unwrap();
 
         compare_byline(
-            BufReader::new(StoreFile::from_str("1 2\n3 4\n", judger::FileType::Plain).file),
-            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::Plain).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4\n", judger::FileType::PLAIN).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::PLAIN).file),
             |_, _, _| Ok(()),
         )
         .unwrap();
 
         compare_byline(
-            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::Plain).file),
-            BufReader::new(StoreFile::from_str("1 2\n3 4\n ", judger::FileType::Plain).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::PLAIN).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4\n ", judger::FileType::PLAIN).file),
             |_, out, ans| {
                 dbg!(out);
                 dbg!(ans);
@@ -392,8 +346,8 @@ mod tests {
         .unwrap_err();
 
         compare_byline(
-            BufReader::new(StoreFile::from_str("1 2\n3 4\n ", judger::FileType::Plain).file),
-            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::Plain).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4\n ", judger::FileType::PLAIN).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::PLAIN).file),
             |_, out, ans| {
                 dbg!(out);
                 dbg!(ans);
@@ -403,8 +357,8 @@ mod tests {
         .unwrap_err();
 
         compare_byline(
-            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::Plain).file),
-            BufReader::new(StoreFile::from_str("1 2\n3 4\n\n", judger::FileType::Plain).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::PLAIN).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4\n\n", judger::FileType::PLAIN).file),
             |_, out, ans| {
                 dbg!(out);
                 dbg!(ans);
@@ -414,8 +368,8 @@ mod tests {
         .unwrap_err();
 
         compare_byline(
-            BufReader::new(StoreFile::from_str("1 2\n3 4\n\n", judger::FileType::Plain).file),
-            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::Plain).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4\n\n", judger::FileType::PLAIN).file),
+            BufReader::new(StoreFile::from_str("1 2\n3 4", judger::FileType::PLAIN).file),
             |_, out, ans| {
                 dbg!(out);
                 dbg!(ans);
@@ -8,8 +8,9 @@ use judger::{
 };
 
 use crate::data::{Data, Rule};
+use anyhow::Context;
 use judger::{JudgeReport, SubtaskReport};
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use store::FsStore;
 
 pub struct Summarizer {
@@ -66,6 +67,14 @@ impl Summarizer {
     }
 }
 
+/// [`JudgeTask::prepare`] 的结果
+pub enum Prepared<S> {
+    /// 准备完成，得到所有测试点共用的状态
+    Ready(S),
+    /// 准备失败（例如编译错误），所有测试点都直接使用这个结果
+    Failed(judger::TaskReport),
+}
+
 pub trait JudgeTask {
     /// task type
     type T: FsStore;
@@ -73,30 +82,67 @@ pub trait JudgeTask {
     type M: FsStore;
     // any owned data always passes a 'static lifetime bound
     type Subm: FsStore + Send + Sync + 'static;
+    /// 一次提交的评测过程中，各个测试点共用的状态（例如编译好的可执行文件）
+    type State;
+
+    /// 在评测所有测试点之前调用一次，完成源文件、checker 的编译等工作。
+    ///
+    /// 产生的文件应当放在工作目录下，并通过 [`JudgeTask::artifacts`] 声明需要保留的文件。
+    fn prepare(
+        judger: &mut impl judger::Judger<LogMessage>,
+        meta: &mut Self::M,
+        subm: &mut Self::Subm,
+    ) -> anyhow::Result<Prepared<Self::State>>;
 
     /// 单个测试点的评测
     ///
-    /// 注意，源文件的编译、checker 的编译等等事情也会放在这里一起做。
-    /// 从“多测试点评测”的概念上看，其最本质的写法就是对不同的测试点，把所有的流程都走一遍。
-    /// 当然我们可以在实现的时候结合缓存系统来提高效率。
+    /// 评测每个测试点之前，工作目录中除 [`JudgeTask::artifacts`] 以外的文件都会被删除。
     fn judge_task(
         judger: &mut impl judger::Judger<LogMessage>,
         meta: &mut Self::M,
         task: &mut Self::T,
         subm: &mut Self::Subm,
+        state: &mut Self::State,
     ) -> anyhow::Result<judger::TaskReport>;
+
+    /// [`JudgeTask::prepare`] 产生的、需要在测试点之间保留的文件，默认为空
+    fn artifacts(_state: &Self::State) -> Vec<&store::Handle> {
+        Vec::new()
+    }
+
+    /// 在所有测试点评测完之后调用（准备失败时不会调用），默认什么也不做
+    fn teardown(
+        _judger: &mut impl judger::Judger<LogMessage>,
+        _meta: &mut Self::M,
+        _state: Self::State,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 /// 通过 channel 发送评测日志
 pub struct MpscJudger {
     wd: store::Handle,
     sender: mpsc::SyncSender<LogMessage>,
+    cpu_pool: Option<Arc<judger::CpuPool>>,
 }
 
 impl MpscJudger {
     pub fn new(wd: store::Handle) -> (Self, mpsc::Receiver<LogMessage>) {
         let (sender, receiver) = std::sync::mpsc::sync_channel::<LogMessage>(128);
-        (Self { wd, sender }, receiver)
+        (
+            Self {
+                wd,
+                sender,
+                cpu_pool: None,
+            },
+            receiver,
+        )
+    }
+    /// 将执行的程序绑定到 `pool` 中空闲的 CPU 上，同时评测的程序不会共用一个 CPU
+    pub fn with_cpu_pool(mut self, pool: Arc<judger::CpuPool>) -> Self {
+        self.cpu_pool = Some(pool);
+        self
     }
 }
 
@@ -109,6 +155,10 @@ impl judger::Judger<LogMessage> for MpscJudger {
         // ignore send error
         let _ = self.sender.send(msg);
     }
+
+    fn cpu_pool(&self) -> Option<&judger::CpuPool> {
+        self.cpu_pool.as_deref()
+    }
 }
 
 /// use thiserror to conveniently define message content
@@ -126,6 +176,35 @@ pub enum LogMessage {
     End,
 }
 
+/// 删除工作目录下除 `keep` 以外的所有文件
+fn clear_working_dir(wd: &store::Handle, keep: &[&store::Handle]) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(wd.path()).context("read working dir")? {
+        let path = entry?.path();
+        if !keep.iter().any(|h| h.path() == path) {
+            store::Handle::new(path).remove_all()?;
+        }
+    }
+    Ok(())
+}
+
+/// 准备失败时所有测试点都使用同一个结果
+fn judge_prepared<J: JudgeTask>(
+    judger: &mut impl judger::Judger<LogMessage>,
+    meta: &mut J::M,
+    task: &mut J::T,
+    subm: &mut J::Subm,
+    state: &mut Prepared<J::State>,
+) -> anyhow::Result<judger::TaskReport> {
+    match state {
+        Prepared::Ready(state) => {
+            clear_working_dir(judger.working_dir(), &J::artifacts(state))
+                .context("clear working dir")?;
+            J::judge_task(judger, meta, task, subm, state)
+        }
+        Prepared::Failed(report) => Ok(report.clone()),
+    }
+}
+
 /// 题目的评测
 ///
 /// 结果中的 time 和 memory 为单个测试点的最大用时 / 内存
@@ -143,7 +222,12 @@ pub fn judge<J>(
 where
     J: JudgeTask,
 {
-    Ok(match &mut data.tasks {
+    judger
+        .working_dir()
+        .prepare_empty_dir()
+        .context("init working dir")?;
+    let mut state = J::prepare(judger, data.meta, subm)?;
+    let report = match &mut data.tasks {
         crate::data::Taskset::Subtasks { subtasks, deps } => {
             judger.runtime_log(LogMessage::StartSubtasks);
             let mut summary = Summarizer::new(Rule::Sum);
@@ -164,7 +248,7 @@ where
                     } else {
                         judger.runtime_log(LogMessage::SubtaskTask(id, tid));
 
-                        let r = J::judge_task(judger, data.meta, task, subm)?;
+                        let r = judge_prepared::<J>(judger, data.meta, task, subm, &mut state)?;
 
                         sub_summary.update(&r.meta, 1.0);
                         subreports.push(Some(r));
@@ -195,8 +279,7 @@ where
                     reports.push(None)
                 } else {
                     judger.runtime_log(LogMessage::TestTask(id));
-                    judger.working_dir().remove_all()?;
-                    let r = J::judge_task(judger, data.meta, task, subm)?;
+                    let r = judge_prepared::<J>(judger, data.meta, task, subm, &mut state)?;
                     summary.update(&r.meta, default_score);
                     reports.push(Some(r));
                 }
@@ -207,5 +290,9 @@ where
                 detail: judger::JudgeDetail::Tests(reports),
             }
         }
-    })
+    };
+    if let Prepared::Ready(state) = state {
+        J::teardown(judger, data.meta, state)?;
+    }
+    Ok(report)
 }
@@ -1,18 +1,15 @@
 use crate::{
     data::StoreFile,
-    judger_framework::{JudgeTask, LogMessage},
+    judger_framework::{JudgeTask, LogMessage, Prepared},
     Checker, Override,
 };
 use anyhow::Context;
 use judger::{
-    sandbox::{
-        unix::{Lim, SingletonConfig},
-        Elapse, Memory,
-    },
+    sandbox::{unix::Lim, Elapse, Memory},
     truncstr::{TruncStr, TRUNCATE_LEN},
     SourceFile,
 };
-use store::FsStore;
+use store::{FsStore, Handle};
 
 #[derive(FsStore, Debug)]
 pub struct Meta {
@@ -58,24 +55,32 @@ pub struct Subm {
 /// 传统题评测
 pub struct Traditional;
 
+/// 传统题各个测试点共用的编译结果
+pub struct Artifacts {
+    /// 选手的源文件，部分 checker（例如 quine）会读取它
+    source: Handle,
+    exec: Handle,
+    compile_log: TruncStr,
+    /// checker 的编译结果，编译失败时记录错误信息
+    checker: Result<Option<Handle>, String>,
+}
+
 impl JudgeTask for Traditional {
     type T = Task;
     type M = Meta;
     type Subm = Subm;
+    type State = Artifacts;
 
-    // 先写了一个粗糙的，后面再来错误处理
-    fn judge_task(
+    fn prepare(
         judger: &mut impl judger::Judger<LogMessage>,
         meta: &mut Self::M,
-        task: &mut Self::T,
         subm: &mut Self::Subm,
-    ) -> anyhow::Result<judger::TaskReport> {
-        judger
-            .working_dir()
-            .prepare_empty_dir()
-            .context("init working dir")?;
-
+    ) -> anyhow::Result<Prepared<Self::State>> {
         let Subm { source } = subm;
+        let source_path = judger
+            .working_dir()
+            .join("main-pre")
+            .with_extension(source.file_type.ext());
 
         let judger::Compilation {
             termination: term,
@@ -91,7 +96,7 @@ impl JudgeTask for Traditional {
 
         // Compile Error
         if !term.status.ok() {
-            return Ok(judger::TaskReport {
+            return Ok(Prepared::Failed(judger::TaskReport {
                 meta: judger::TaskMeta {
                     score_rate: 0.0,
                     status: judger::Status::CompileError(Some(term.status)),
@@ -99,32 +104,60 @@ impl JudgeTask for Traditional {
                     memory: term.memory,
                 },
                 payload: vec![("compile log".into(), log_payload)],
-            });
+            }));
         }
 
         let mut execfile = execfile.context("compile succeed but execfile not found")?;
-        // dbg!(execfile.metadata().unwrap().permissions());
         let exec = judger.copy_file(&mut execfile, "main")?;
+        let checker = meta.checker.prepare(judger).map_err(|e| format!("{e:#?}"));
 
+        Ok(Prepared::Ready(Artifacts {
+            source: source_path,
+            exec,
+            compile_log: log_payload,
+            checker,
+        }))
+    }
+
+    fn artifacts(state: &Self::State) -> Vec<&Handle> {
+        let mut files = vec![&state.source, &state.exec];
+        if let Ok(Some(checker)) = &state.checker {
+            files.push(checker);
+        }
+        files
+    }
+
+    fn judge_task(
+        judger: &mut impl judger::Judger<LogMessage>,
+        meta: &mut Self::M,
+        task: &mut Self::T,
+        subm: &mut Self::Subm,
+        state: &mut Self::State,
+    ) -> anyhow::Result<judger::TaskReport> {
+        let file_type = &subm.source.file_type;
         let input = judger.copy_store_file(&mut task.input, "input")?;
         let answer = judger.copy_store_file(&mut task.output, "answer")?;
 
         let output = judger.clear_dest("output")?;
         let log = judger.clear_dest("log")?;
 
-        let s = SingletonConfig::new(exec.to_string())
-            .push_args(["main"])
+        let time_limit = file_type.scale_time(meta.time_limit);
+        let memory_limit = file_type.scale_memory(meta.memory_limit);
+        let s = file_type
+            .exec_sandbox(&state.exec)?
             .stdin(input.to_string())
             .stdout(output.to_string())
             .stderr(log.to_string())
             .set_limits(|_| judger::sandbox::unix::Limitation {
-                real_time: Lim::Double(meta.time_limit, meta.time_limit * 1.1),
-                cpu_time: meta.time_limit.into(),
-                virtual_memory: meta.memory_limit.into(),
-                real_memory: meta.memory_limit.into(),
-                stack_memory: meta.memory_limit.into(),
+                real_time: Lim::Double(time_limit, time_limit * 1.1),
+                cpu_time: time_limit.into(),
+                virtual_memory: memory_limit.into(),
+                real_memory: memory_limit.into(),
+                stack_memory: memory_limit.into(),
                 output_memory: meta.output_limit.into(),
                 fileno: 10.into(),
+                nproc: 1.into(),
+                instructions: None,
             });
 
         let term = judger.exec_sandbox(s)?;
@@ -133,14 +166,16 @@ impl JudgeTask for Traditional {
         let mut report = judger::TaskReport {
             meta: judger::TaskMeta {
                 score_rate: 0.0,
-                status: term.status.into(),
+                status: (&term).into(),
                 time: term.cpu_time,
                 memory: term.memory,
             },
             payload: Vec::new(),
         };
         report.meta.score_rate = report.meta.status.direct_score_rate();
-        report.payload.push(("compile log".into(), log_payload));
+        report
+            .payload
+            .push(("compile log".into(), state.compile_log.clone()));
         let _ = report.add_payload("stdin", &input);
         let _ = report.add_payload("stdout", &output);
         let _ = report.add_payload("answer", &answer);
@@ -151,7 +186,13 @@ impl JudgeTask for Traditional {
         }
 
         // check answer
-        let r = meta.checker.check(judger, &input, &output, &answer);
+        let r = match &state.checker {
+            Ok(checker) => meta
+                .checker
+                .check_prepared(judger, checker.as_ref(), &input, &output, &answer)
+                .map_err(|e| format!("{e:#?}")),
+            Err(e) => Err(e.clone()),
+        };
 
         report.meta.score_rate = r.as_ref().map(|o| o.0).unwrap_or(0.);
         report.payload.push((
@@ -159,7 +200,7 @@ impl JudgeTask for Traditional {
             TruncStr::new(
                 match r {
                     Ok(s) => s.1,
-                    Err(s) => format!("{s:#?}"),
+                    Err(s) => s,
                 },
                 TRUNCATE_LEN,
             ),
@@ -171,13 +212,31 @@ impl JudgeTask for Traditional {
 #[cfg(test)]
 mod tests {
     use super::{Meta, Subm, Task, Traditional};
-    use crate::{data::StoreFile, judger_framework::JudgeTask, Checker};
+    use crate::{
+        data::StoreFile,
+        judger_framework::{JudgeTask, LogMessage, Prepared},
+        Checker,
+    };
     use judger::{
         sandbox::{Elapse, Memory},
         DefaultJudger, SourceFile,
     };
     use store::Handle;
 
+    fn judge_single(
+        jd: &mut DefaultJudger<LogMessage>,
+        meta: &mut Meta,
+        task: &mut Task,
+        subm: &mut Subm,
+    ) -> judger::TaskReport {
+        match Traditional::prepare(jd, meta, subm).unwrap() {
+            Prepared::Ready(mut state) => {
+                Traditional::judge_task(jd, meta, task, subm, &mut state).unwrap()
+            }
+            Prepared::Failed(report) => report,
+        }
+    }
+
     #[test]
     fn test_a_plus_b() {
         let dir = tempfile::tempdir().unwrap();
@@ -190,8 +249,8 @@ mod tests {
             output_limit: Memory::from_mb(64),
         };
         let mut task = Task {
-            input: StoreFile::from_str("1 2", judger::FileType::Plain),
-            output: StoreFile::from_str("3\n", judger::FileType::Plain),
+            input: StoreFile::from_str("1 2", judger::FileType::PLAIN),
+            output: StoreFile::from_str("3\n", judger::FileType::PLAIN),
         };
         let mut subm = Subm {
             source: SourceFile::from_str(
@@ -203,12 +262,69 @@ mod tests {
                             cout << a + b << endl;
                         }
                         "#,
-                judger::FileType::GnuCpp14O2,
+                judger::FileType::GNU_CPP14_O2,
             ),
         };
 
-        let report = Traditional::judge_task(&mut jd, &mut meta, &mut task, &mut subm).unwrap();
+        let report = judge_single(&mut jd, &mut meta, &mut task, &mut subm);
         dbg!(&report);
         assert_eq!(judger::Status::Good, report.meta.status);
     }
+
+    #[test]
+    fn test_a_plus_b_python() {
+        let dir = tempfile::tempdir().unwrap();
+        let wd = Handle::new(dir);
+        let mut jd = DefaultJudger::new(wd, None);
+        let mut meta = Meta {
+            checker: Checker::FileCmp,
+            time_limit: Elapse::from_sec(5),
+            memory_limit: Memory::from_mb(256),
+            output_limit: Memory::from_mb(64),
+        };
+        let mut task = Task {
+            input: StoreFile::from_str("1 2", judger::FileType::PLAIN),
+            output: StoreFile::from_str("3\n", judger::FileType::PLAIN),
+        };
+        let mut subm = Subm {
+            source: SourceFile::from_str(
+                "a, b = map(int, input().split())\nprint(a + b)\n",
+                judger::FileType::PYTHON3,
+            ),
+        };
+
+        let report = judge_single(&mut jd, &mut meta, &mut task, &mut subm);
+        assert_eq!(judger::Status::Good, report.meta.status, "{report:?}");
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let wd = Handle::new(dir);
+        let mut jd = DefaultJudger::new(wd, None);
+        let mut meta = Meta {
+            checker: Checker::FileCmp,
+            time_limit: Elapse::from_sec(5),
+            memory_limit: Memory::from_mb(256),
+            output_limit: Memory::from_mb(1),
+        };
+        let mut task = Task {
+            input: StoreFile::from_str("1 2", judger::FileType::PLAIN),
+            output: StoreFile::from_str("3\n", judger::FileType::PLAIN),
+        };
+        let mut subm = Subm {
+            source: SourceFile::from_str(
+                r#"#include<iostream>
+                        using namespace std;
+                        int main() {
+                            for (int i = 0; i < 1000000; i++) cout << i << endl;
+                        }
+                        "#,
+                judger::FileType::GNU_CPP14_O2,
+            ),
+        };
+
+        let report = judge_single(&mut jd, &mut meta, &mut task, &mut subm);
+        assert_eq!(judger::Status::OutputLimitExceeded, report.meta.status);
+    }
 }
@@ -49,8 +49,8 @@ int main() {
 pub fn a_plus_b_data() -> StandardProblem {
     fn gen_a_plus_b_task(a: i32, b: i32) -> crate::prelude::traditional::Task {
         crate::prelude::traditional::Task {
-            input: StoreFile::from_str(format!("{a} {b}"), judger::FileType::Plain),
-            output: StoreFile::from_str((a + b).to_string(), judger::FileType::Plain),
+            input: StoreFile::from_str(format!("{a} {b}"), judger::FileType::PLAIN),
+            output: StoreFile::from_str((a + b).to_string(), judger::FileType::PLAIN),
         }
     }
 
@@ -131,7 +131,7 @@ int main() {
     return 0;
 }
 "#,
-            judger::FileType::GnuCpp14O2,
+            judger::FileType::GNU_CPP14_O2,
         ),
     }
 }
@@ -149,7 +149,7 @@ int main() {
     return 0;
 }
 "#,
-            judger::FileType::GnuCpp14O2,
+            judger::FileType::GNU_CPP14_O2,
         ),
     }
 }
@@ -189,7 +189,7 @@ pub fn quine_data() -> StandardProblem {
             checker: crate::Checker::CABI {
                 source: SourceFile::from_str(
                     include_str!("../tests/quine_checker.rs"),
-                    judger::FileType::Rust,
+                    judger::FileType::RUST,
                 ),
             },
             time_limit: crate::Elapse::from(1000u64),
@@ -198,8 +198,8 @@ pub fn quine_data() -> StandardProblem {
         })
         .set_data(crate::data::Taskset::Tests {
             tasks: vec![crate::prelude::traditional::Task {
-                input: StoreFile::from_str("", judger::FileType::Plain),
-                output: StoreFile::from_str("", judger::FileType::Plain),
+                input: StoreFile::from_str("", judger::FileType::PLAIN),
+                output: StoreFile::from_str("", judger::FileType::PLAIN),
             }],
         }),
     )
@@ -231,7 +231,7 @@ pub fn quine_std() -> crate::prelude::traditional::Subm {
         source: SourceFile::from_str(
             r##"#include<cstdio>
 char*s="#include<cstdio>%cchar*s=%c%s%c;main(){printf(s,10,34,s,34);}";main(){printf(s,10,34,s,34);}"##,
-            judger::FileType::GnuCpp14O2,
+            judger::FileType::GNU_CPP14_O2,
         ),
     }
 }
@@ -282,4 +282,113 @@ mod tests {
 
         Ok(())
     }
+
+    /// 记录编译次数的 judger
+    struct CountingJudger {
+        inner: DefaultJudger<judger_framework::LogMessage>,
+        compiles: std::cell::Cell<usize>,
+    }
+
+    impl judger::Judger<judger_framework::LogMessage> for CountingJudger {
+        fn working_dir(&self) -> &Handle {
+            self.inner.working_dir()
+        }
+        fn runtime_log(&mut self, msg: judger_framework::LogMessage) {
+            self.inner.runtime_log(msg)
+        }
+        fn compile(
+            &self,
+            file: &mut SourceFile,
+            name: &str,
+        ) -> anyhow::Result<judger::Compilation> {
+            self.compiles.set(self.compiles.get() + 1);
+            self.inner.compile(file, name)
+        }
+    }
+
+    #[test]
+    fn test_compile_once() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut judger = CountingJudger {
+            inner: DefaultJudger::new(Handle::new(dir.path()), None),
+            compiles: Default::default(),
+        };
+
+        let StandardProblem::Traditional(mut data) = a_plus_b_data();
+        let mut data = data.get_data_mut();
+        let mut subm = a_plus_b_std();
+        let report = judger_framework::judge::<Traditional>(&mut data, &mut judger, &mut subm)?;
+        assert!((report.meta.score_rate - 1.).abs() < 1e-5);
+        assert_eq!(judger.compiles.get(), 1);
+
+        // 编译错误时每个测试点都得到编译错误
+        judger.compiles.set(0);
+        let mut subm = crate::prelude::traditional::Subm {
+            source: SourceFile::from_str("int main() {", judger::FileType::GNU_CPP14_O2),
+        };
+        let report = judger_framework::judge::<Traditional>(&mut data, &mut judger, &mut subm)?;
+        assert!(matches!(
+            report.meta.status,
+            judger::Status::CompileError(_)
+        ));
+        assert_eq!(judger.compiles.get(), 1);
+        Ok(())
+    }
+
+    /// 检查测试点之间工作目录会被清空，但保留 prepare 的产物
+    struct Leftover;
+
+    impl judger_framework::JudgeTask for Leftover {
+        type T = crate::prelude::traditional::Task;
+        type M = crate::prelude::traditional::Meta;
+        type Subm = crate::prelude::traditional::Subm;
+        type State = Handle;
+
+        fn prepare(
+            judger: &mut impl judger::Judger<judger_framework::LogMessage>,
+            _meta: &mut Self::M,
+            _subm: &mut Self::Subm,
+        ) -> anyhow::Result<judger_framework::Prepared<Self::State>> {
+            let artifact = judger.create_source_file("", "artifact")?;
+            Ok(judger_framework::Prepared::Ready(artifact))
+        }
+
+        fn artifacts(state: &Self::State) -> Vec<&Handle> {
+            vec![state]
+        }
+
+        fn judge_task(
+            judger: &mut impl judger::Judger<judger_framework::LogMessage>,
+            _meta: &mut Self::M,
+            _task: &mut Self::T,
+            _subm: &mut Self::Subm,
+            state: &mut Self::State,
+        ) -> anyhow::Result<judger::TaskReport> {
+            assert!(state.path().is_file());
+            // 上一个测试点留下的文件应当已被删除
+            judger.create_source_file("", "leftover")?;
+            Ok(judger::TaskReport {
+                meta: judger::TaskMeta {
+                    score_rate: 1.0,
+                    status: judger::Status::Good,
+                    time: 0.into(),
+                    memory: 0.into(),
+                },
+                payload: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_clear_working_dir() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut judger = DefaultJudger::new(Handle::new(dir.path()), None);
+
+        let StandardProblem::Traditional(mut data) = a_plus_b_data();
+        let mut data = data.get_data_mut();
+        let mut subm = a_plus_b_std();
+        let report = judger_framework::judge::<Leftover>(&mut data, &mut judger, &mut subm)?;
+        assert!((report.meta.score_rate - 1.).abs() < 1e-5);
+        Ok(())
+    }
 }
@@ -7,11 +7,11 @@ fn test_testlib() -> anyhow::Result<()> {
     let mut checker = Checker::TestlibChecker {
         testlib_header: StoreFile::from_str(
             include_str!("assets/testlib.txt"),
-            judger::FileType::GnuCpp14O2,
+            judger::FileType::GNU_CPP14_O2,
         ),
         checker: SourceFile::from_str(
             include_str!("assets/acmp.txt"),
-            judger::FileType::GnuCpp14O2,
+            judger::FileType::GNU_CPP14_O2,
         ),
     };
 
@@ -20,15 +20,15 @@ fn test_testlib() -> anyhow::Result<()> {
     let mut judger = DefaultJudger::new(Handle::new(wd.path()), None);
     judger.runtime_log("");
     let input = judger.copy_store_file(
-        &mut StoreFile::from_str("0", judger::FileType::Plain),
+        &mut StoreFile::from_str("0", judger::FileType::PLAIN),
         "input",
     )?;
     let output = judger.copy_store_file(
-        &mut StoreFile::from_str("0", judger::FileType::Plain),
+        &mut StoreFile::from_str("0", judger::FileType::PLAIN),
         "output",
     )?;
     let answer = judger.copy_store_file(
-        &mut StoreFile::from_str("0.0001", judger::FileType::Plain),
+        &mut StoreFile::from_str("0.0001", judger::FileType::PLAIN),
         "answer",
     )?;
 
@@ -55,7 +55,7 @@ extern "C" {
         return 1.0;
     }
 }"#,
-            judger::FileType::GnuCpp14O2,
+            judger::FileType::GNU_CPP14_O2,
         ),
     };
 
@@ -64,15 +64,15 @@ extern "C" {
     let mut judger = DefaultJudger::new(Handle::new(wd.path()), None);
     judger.runtime_log("");
     let input = judger.copy_store_file(
-        &mut StoreFile::from_str("0", judger::FileType::Plain),
+        &mut StoreFile::from_str("0", judger::FileType::PLAIN),
         "input",
     )?;
     let output = judger.copy_store_file(
-        &mut StoreFile::from_str("0", judger::FileType::Plain),
+        &mut StoreFile::from_str("0", judger::FileType::PLAIN),
         "output",
     )?;
     let answer = judger.copy_store_file(
-        &mut StoreFile::from_str("0.0001", judger::FileType::Plain),
+        &mut StoreFile::from_str("0.0001", judger::FileType::PLAIN),
         "answer",
     )?;
 
@@ -93,7 +93,7 @@ pub extern "C" fn check() -> f32 {
     println!("run rust checker!, v = {v:?}");
     1.0
 }"#,
-            judger::FileType::Rust,
+            judger::FileType::RUST,
         ),
     };
 
@@ -102,15 +102,15 @@ pub extern "C" fn check() -> f32 {
     let mut judger = DefaultJudger::new(Handle::new(wd.path()), None);
     judger.runtime_log("");
     let input = judger.copy_store_file(
-        &mut StoreFile::from_str("0", judger::FileType::Plain),
+        &mut StoreFile::from_str("0", judger::FileType::PLAIN),
         "input",
     )?;
     let output = judger.copy_store_file(
-        &mut StoreFile::from_str("0", judger::FileType::Plain),
+        &mut StoreFile::from_str("0", judger::FileType::PLAIN),
         "output",
     )?;
     let answer = judger.copy_store_file(
-        &mut StoreFile::from_str("0.0001", judger::FileType::Plain),
+        &mut StoreFile::from_str("0.0001", judger::FileType::PLAIN),
         "answer",
     )?;
 
@@ -119,3 +119,20 @@ pub extern "C" fn check() -> f32 {
 
     Ok(())
 }
+
+#[test]
+fn test_c_abi_unsupported() -> anyhow::Result<()> {
+    let mut checker = Checker::CABI {
+        source: SourceFile::from_str("def check(): return 1.0", judger::FileType::PYTHON3),
+    };
+
+    let wd = tempfile::tempdir().unwrap();
+    let judger = DefaultJudger::<&str>::new(Handle::new(wd.path()), None);
+
+    let err = checker.prepare(&judger).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("does not support C ABI"),
+        "{err:#}"
+    );
+    Ok(())
+}
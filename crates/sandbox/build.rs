@@ -5,12 +5,16 @@ fn main() {
     {
         // Tell Cargo that if the given file changes, to rerun this build script.
         println!("cargo:rerun-if-changed=src/unix/utils.c");
+        println!("cargo:rerun-if-changed=src/unix/seccomp.c");
+        println!("cargo:rerun-if-changed=src/unix/isolate.c");
         println!("cargo:rerun-if-changed=src/unix/sigutils.h");
         println!("cargo:rerun-if-changed=src/unix/share.h");
         println!("cargo:rerun-if-changed=src/unix/sio.h");
 
         cc::Build::new()
             .file("src/unix/utils.c")
+            .file("src/unix/seccomp.c")
+            .file("src/unix/isolate.c")
             .compile("sandboxunixutils");
 
         // Write the bindings to the $OUT_DIR/bindings.rs file.
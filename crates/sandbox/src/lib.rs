@@ -5,6 +5,13 @@
 Execute program in sandbox. Currently, this sandbox is not safe enough to be run outside
 of a container (e. g. docker).
 
+On Linux, the syscalls of the executed program can be restricted by a seccomp-bpf filter,
+see [`unix::SyscallPolicy`], and the program can be isolated by namespaces with a minimal
+root directory, see [`unix::Isolation`].
+
+With the `async` feature, a program can be executed in a tokio runtime and killed while
+running, see `unix::SingletonHandle`.
+
 Although tests can be passed on MacOS, we do not recommend you running sandbox on it,
 since the current implementation is vulnerable due to its bad support of `setrlimit`.
 */
@@ -34,10 +41,14 @@ pub enum Status {
     MemoryLimitExceeded,
     /// 超出时间限制
     TimeLimitExceeded,
-    // 输出文件大小超出限制
-    // OutputLimitExceeded,
-    // 调用了被禁止的系统调用
-    // DangerousSyscall,
+    /// 输出文件大小超出限制
+    OutputLimitExceeded,
+    /// 调用了被禁止的系统调用（附带系统调用号）
+    DangerousSyscall(i32),
+    /// 因创建的进程（线程）数超出限制而运行错误（附带状态码）
+    ProcessLimitExceeded(i32),
+    /// CPU 时间长时间没有增长（例如阻塞在读入或者休眠），被提前终止
+    IdlenessLimitExceeded,
 }
 
 impl Status {
@@ -58,6 +69,51 @@ pub struct Termination {
     pub cpu_time: Elapse,
     /// 实际占用内存 (byte)
     pub memory: Memory,
+    /// 正常退出时的返回值
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// 被信号终止时的信号
+    #[serde(default)]
+    pub signal: Option<Signal>,
+    /// 主动让出 CPU 的次数（例如等待 IO）
+    #[serde(default)]
+    pub voluntary_switches: u64,
+    /// 被抢占 CPU 的次数
+    #[serde(default)]
+    pub involuntary_switches: u64,
+    /// 不需要 IO 的缺页次数
+    #[serde(default)]
+    pub minor_page_faults: u64,
+    /// 需要 IO 的缺页次数
+    #[serde(default)]
+    pub major_page_faults: u64,
+    /// 在用户态执行的指令数，为 None 表示没有统计或者不支持，见 `unix::Limitation::instructions`
+    #[serde(default)]
+    pub instructions: Option<u64>,
+    /// 捕获到内存中的标准输出，见 `unix::SingletonConfig::capture_stdout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<Vec<u8>>,
+    /// 捕获到内存中的标准错误输出，见 `unix::SingletonConfig::capture_stderr`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<Vec<u8>>,
+}
+
+/// 终止程序的信号
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    /// 信号值（与平台有关）
+    pub number: i32,
+    /// 信号名，例如 `SIGSEGV`
+    pub name: Option<String>,
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "signal {}", self.number),
+        }
+    }
 }
 
 /// 在沙箱中执行一系列的任务，返回相应的结果
@@ -104,6 +160,10 @@ impl Elapse {
     pub fn sec(self) -> u64 {
         self.0 / 1000
     }
+    /// 输出以秒为单位的时间（向上取整）
+    pub fn sec_ceil(self) -> u64 {
+        self.0.div_ceil(1000)
+    }
     /// 输出以毫秒为单位的时间
     pub fn ms(self) -> u64 {
         self.0
@@ -124,11 +184,12 @@ impl Display for Elapse {
     }
 }
 
+/// 支持带单位的时间，例如 `1.5s`、`200ms`、`2min`，不带单位时以毫秒为单位
 impl FromStr for Elapse {
-    type Err = <u64 as FromStr>::Err;
+    type Err = ParseLimitError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(s.parse()?))
+        parse_with_unit(s, &[("ms", 1), ("s", 1000), ("min", 60_000)]).map(Self)
     }
 }
 
@@ -166,11 +227,29 @@ impl Display for Memory {
     }
 }
 
+/// 支持带单位的内存大小，例如 `256MiB`、`64M`、`1.5GB`，不带单位时以字节为单位。
+///
+/// 单位不区分大小写，与 [`Memory::from_mb`] 一致，`K`、`KB`、`KiB` 都表示 1024 字节，以此类推。
 impl FromStr for Memory {
-    type Err = <u64 as FromStr>::Err;
+    type Err = ParseLimitError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(s.parse()?))
+        const K: u64 = 1 << 10;
+        const M: u64 = 1 << 20;
+        const G: u64 = 1 << 30;
+        let units = [
+            ("b", 1),
+            ("k", K),
+            ("kb", K),
+            ("kib", K),
+            ("m", M),
+            ("mb", M),
+            ("mib", M),
+            ("g", G),
+            ("gb", G),
+            ("gib", G),
+        ];
+        parse_with_unit(s, &units).map(Self)
     }
 }
 
@@ -180,6 +259,14 @@ impl From<u64> for Memory {
     }
 }
 
+impl std::ops::Mul<f64> for Memory {
+    type Output = Memory;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Memory((self.0 as f64 * rhs) as u64)
+    }
+}
+
 impl Memory {
     /// 输出以字节为单位的时间
     pub fn byte(self) -> u64 {
@@ -201,6 +288,50 @@ impl Memory {
     }
 }
 
+/// 解析资源限制时的错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct ParseLimitError(String);
+
+impl ParseLimitError {
+    pub(crate) fn new(msg: impl Into<String>) -> Self {
+        Self(msg.into())
+    }
+}
+
+/// 解析带单位的数值，`units` 是单位（不区分大小写）及其对应的倍数，不带单位时倍数为 1
+fn parse_with_unit(s: &str, units: &[(&str, u64)]) -> Result<u64, ParseLimitError> {
+    let s = s.trim();
+    let pos = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(pos);
+    let unit = unit.trim();
+    let scale = if unit.is_empty() {
+        1
+    } else {
+        units
+            .iter()
+            .find(|(u, _)| u.eq_ignore_ascii_case(unit))
+            .map(|(_, k)| *k)
+            .ok_or_else(|| ParseLimitError(format!("unknown unit {unit:?} in {s:?}")))?
+    };
+    if let Ok(v) = num.parse::<u64>() {
+        return v
+            .checked_mul(scale)
+            .ok_or_else(|| ParseLimitError(format!("{s:?} is too large")));
+    }
+    let v: f64 = num
+        .parse()
+        .map_err(|_| ParseLimitError(format!("invalid number {num:?} in {s:?}")))?;
+    let v = (v * scale as f64).round();
+    // `as` saturates silently
+    if !v.is_finite() || v >= u64::MAX as f64 {
+        return Err(ParseLimitError(format!("{s:?} is too large")));
+    }
+    Ok(v as u64)
+}
+
 /// copy from nix, create a null-terminate c-style string array.
 /// This function is not necessarily async-signal safe
 #[cfg(feature = "exec_sandbox")]
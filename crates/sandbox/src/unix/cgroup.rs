@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+/// 统计和限制程序资源占用的方式
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum ResourceBackend {
+    /// 使用 rlimit 限制资源，使用 rusage 统计资源
+    #[default]
+    #[serde(rename = "rlimit")]
+    Rlimit,
+    /// 在给定的 cgroup v2 目录下为每次运行创建一个子 cgroup，
+    /// 使用 `memory.peak` 和 `cpu.stat` 统计资源，并设置 `memory.max` 和 `pids.max`。
+    ///
+    /// `memory.peak` 包含页缓存（例如写入输出文件产生的缓存），因此统计的内存占用会减去
+    /// 运行结束时 `memory.stat` 中的 `file`，并且不少于 rusage 中的最大常驻内存。
+    /// 页缓存在达到 `memory.max` 时会先被回收，不会导致程序被 OOM killer 终止。
+    ///
+    /// 该目录需要被委派给当前用户，且其中不能有进程（否则无法为子 cgroup 启用控制器）。
+    /// 如果无法使用 cgroup（例如没有委派，或者内核不支持 `memory.peak`），会退回到 rlimit 的方式。
+    #[serde(rename = "cgroup")]
+    Cgroup(String),
+}
+
+#[cfg(feature = "exec_sandbox")]
+pub(crate) use imp::Cgroup;
+
+#[cfg(feature = "exec_sandbox")]
+mod imp {
+    use crate::{unix::sigsafe, Elapse, Memory};
+    use std::{
+        fs::{self, File, OpenOptions},
+        io::{self, Write},
+        os::fd::AsRawFd,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static CGROUP_ID: AtomicUsize = AtomicUsize::new(0);
+
+    fn read_key(path: impl AsRef<Path>, key: &str) -> io::Result<u64> {
+        let content = fs::read_to_string(path)?;
+        content
+            .lines()
+            .find_map(|line| {
+                let (k, v) = line.split_once(' ')?;
+                (k == key).then(|| v.trim().parse().ok()).flatten()
+            })
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("key {key} not found"))
+            })
+    }
+
+    /// 一次运行所使用的 cgroup v2 叶子节点，drop 时会被删除
+    pub struct Cgroup {
+        path: PathBuf,
+        procs: File,
+    }
+
+    impl Cgroup {
+        /// 在 `parent` 下创建一个子 cgroup，并设置内存和进程数上限
+        pub fn create(
+            parent: impl AsRef<Path>,
+            memory_max: Memory,
+            pids_max: u64,
+        ) -> io::Result<Self> {
+            let parent = parent.as_ref();
+            let controllers = fs::read_to_string(parent.join("cgroup.controllers"))?;
+            let controllers: Vec<&str> = controllers.split_whitespace().collect();
+            if !controllers.contains(&"memory") || !controllers.contains(&"pids") {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "memory or pids controller is not available",
+                ));
+            }
+            let subtree = fs::read_to_string(parent.join("cgroup.subtree_control"))?;
+            let subtree: Vec<&str> = subtree.split_whitespace().collect();
+            if !subtree.contains(&"memory") || !subtree.contains(&"pids") {
+                fs::write(parent.join("cgroup.subtree_control"), "+memory +pids")?;
+            }
+
+            let path = parent.join(format!(
+                "zroj-sandbox-{}-{}",
+                std::process::id(),
+                CGROUP_ID.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir(&path)?;
+            // the directory is removed on drop from now on
+            let procs = OpenOptions::new()
+                .write(true)
+                .open(path.join("cgroup.procs"));
+            let cg = Self {
+                procs: match procs {
+                    Ok(f) => f,
+                    Err(e) => {
+                        let _ = fs::remove_dir(&path);
+                        return Err(e);
+                    }
+                },
+                path,
+            };
+            if !cg.path.join("memory.peak").is_file() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "memory.peak is not supported",
+                ));
+            }
+            fs::write(
+                cg.path.join("memory.max"),
+                u64::from(memory_max).to_string(),
+            )?;
+            // swap is not counted in memory.max
+            let _ = fs::write(cg.path.join("memory.swap.max"), "0");
+            fs::write(cg.path.join("pids.max"), pids_max.to_string())?;
+            Ok(cg)
+        }
+        /// 可写的 `cgroup.procs` 文件，向其写入 `0` 即可将当前进程加入该 cgroup。
+        ///
+        /// 该文件描述符带有 `O_CLOEXEC`，不会被被测程序继承。
+        pub fn procs_fd(&self) -> i32 {
+            self.procs.as_raw_fd()
+        }
+        /// 内存占用的峰值（包括页缓存）
+        pub fn memory_peak(&self) -> io::Result<Memory> {
+            let s = fs::read_to_string(self.path.join("memory.peak"))?;
+            let v: u64 = s
+                .trim()
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid memory.peak"))?;
+            Ok(Memory::from(v))
+        }
+        /// 不计页缓存的内存占用峰值，即 `memory.peak` 减去当前的页缓存大小。
+        ///
+        /// 程序运行结束后页缓存仍然计入该 cgroup，而匿名内存已经被释放，
+        /// 因此需要在程序结束之后调用。
+        pub fn memory_peak_without_cache(&self) -> io::Result<Memory> {
+            let peak = u64::from(self.memory_peak()?);
+            let file = read_key(self.path.join("memory.stat"), "file")?;
+            Ok(Memory::from(peak.saturating_sub(file)))
+        }
+        /// 用户态和内核态 CPU 时间之和
+        pub fn cpu_time(&self) -> io::Result<Elapse> {
+            let usec = read_key(self.path.join("cpu.stat"), "usage_usec")?;
+            Ok(Elapse::from(usec / 1000))
+        }
+        /// 是否有进程因超出 `memory.max` 被 OOM killer 终止
+        pub fn oom_killed(&self) -> bool {
+            read_key(self.path.join("memory.events"), "oom_kill").is_ok_and(|n| n > 0)
+        }
+        /// 是否有 fork 因超出 `pids.max` 而失败
+        pub fn pids_exceeded(&self) -> bool {
+            read_key(self.path.join("pids.events"), "max").is_ok_and(|n| n > 0)
+        }
+    }
+
+    impl Drop for Cgroup {
+        fn drop(&mut self) {
+            // kill the remaining processes, e. g. orphaned children of the tested program
+            if let Ok(mut f) = OpenOptions::new()
+                .write(true)
+                .open(self.path.join("cgroup.kill"))
+            {
+                let _ = f.write_all(b"1");
+            }
+            // the killed processes may not have been released yet
+            for _ in 0..100 {
+                match fs::remove_dir(&self.path) {
+                    Err(e) if e.raw_os_error() == Some(sigsafe::EBUSY as i32) => {
+                        std::thread::sleep(std::time::Duration::from_millis(1))
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,89 @@
-use super::Limitation;
-use serde::{Deserialize, Serialize};
+use super::{Isolation, Limitation, ResourceBackend, SyscallPolicy};
+use crate::{Elapse, Memory};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 被测程序的环境变量，默认为空。固定的环境变量会覆盖继承的值。
+///
+/// 序列化为 `KEY=VALUE` 格式的列表，继承的环境变量在序列化时就从当前进程读取，
+/// 因此通过 `zroj-sandbox run` 或者 `zroj-sandbox serve` 执行时，继承的是生成配置的进程
+/// （例如评测端）的环境变量，而不是沙盒进程的。
+/// 反序列化时也接受 `{"inherit": [..], "vars": [..]}` 的形式，
+/// 此时继承的环境变量在构建 [`super::Singleton`] 时从沙盒进程读取。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvPolicy {
+    inherit: Vec<String>,
+    vars: Vec<(String, String)>,
+}
+
+impl EnvPolicy {
+    /// 从当前进程继承这些环境变量（如果存在的话）
+    pub fn inherit<'a>(mut self, keys: impl IntoIterator<Item = &'a str>) -> Self {
+        self.inherit.extend(keys.into_iter().map(String::from));
+        self
+    }
+    /// 设置一个固定的环境变量
+    pub fn var(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        let (key, value) = (key.as_ref().to_string(), value.as_ref().to_string());
+        match self.vars.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.vars.push((key, value)),
+        }
+        self
+    }
+    /// 合并另一个策略，其中的固定环境变量优先
+    pub fn merge(mut self, other: EnvPolicy) -> Self {
+        self.inherit.extend(other.inherit);
+        for (k, v) in other.vars {
+            self = self.var(k, v);
+        }
+        self
+    }
+    /// 生成 `KEY=VALUE` 格式的环境变量列表
+    pub fn resolve(&self) -> Vec<String> {
+        let mut r = Vec::new();
+        for key in &self.inherit {
+            if self.vars.iter().any(|(k, _)| k == key) || r.iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            if let Ok(value) = std::env::var(key) {
+                r.push((key.clone(), value));
+            }
+        }
+        r.extend(self.vars.iter().cloned());
+        r.into_iter().map(|(k, v)| format!("{k}={v}")).collect()
+    }
+}
+
+impl Serialize for EnvPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.resolve().serialize(serializer)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnvPolicyRepr {
+    /// `KEY=VALUE` 格式的列表
+    List(Vec<String>),
+    Policy {
+        #[serde(default)]
+        inherit: Vec<String>,
+        #[serde(default)]
+        vars: Vec<(String, String)>,
+    },
+}
+
+impl<'de> Deserialize<'de> for EnvPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match EnvPolicyRepr::deserialize(deserializer)? {
+            EnvPolicyRepr::List(list) => list.iter().fold(Self::default(), |p, arg| {
+                let (key, value) = arg.split_once('=').unwrap_or((arg, ""));
+                p.var(key, value)
+            }),
+            EnvPolicyRepr::Policy { inherit, vars } => Self { inherit, vars },
+        })
+    }
+}
 
 /// Serializable config format for singleton
 #[derive(Debug, Serialize, Deserialize)]
@@ -7,10 +91,41 @@ pub struct SingletonConfig {
     limits: Limitation,
     exec_path: String,
     arguments: Vec<String>,
-    envs: Vec<String>,
+    #[serde(default)]
+    envs: EnvPolicy,
     stdin: Option<String>,
     stdout: Option<String>,
     stderr: Option<String>,
+    #[serde(default)]
+    syscalls: SyscallPolicy,
+    #[serde(default)]
+    backend: ResourceBackend,
+    #[serde(default)]
+    isolation: Option<Isolation>,
+    #[serde(default)]
+    cpus: Vec<u32>,
+    #[serde(default)]
+    no_aslr: bool,
+    #[serde(default)]
+    nice: Option<i32>,
+    #[serde(default)]
+    idle_limit: Option<Elapse>,
+    #[serde(default)]
+    stdin_data: Option<Vec<u8>>,
+    #[serde(default)]
+    capture_stdout: Option<Memory>,
+    #[serde(default)]
+    capture_stderr: Option<Memory>,
+    #[serde(default)]
+    uid: Option<u32>,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    groups: Vec<u32>,
+    #[serde(default)]
+    count_instructions: bool,
+    #[serde(default)]
+    scratch: Option<Memory>,
 }
 
 #[cfg(feature = "exec_sandbox")]
@@ -28,12 +143,31 @@ impl From<SingletonConfig> for super::Singleton {
                 .collect(),
             envs: value
                 .envs
+                .resolve()
                 .into_iter()
                 .map(|s| CString::new(s).unwrap())
                 .collect(),
             stdin: value.stdin.map(|s| CString::new(s).unwrap()),
             stdout: CString::new(value.stdout.unwrap_or("/dev/null".to_string())).unwrap(),
             stderr: CString::new(value.stderr.unwrap_or("/dev/null".to_string())).unwrap(),
+            syscalls: value.syscalls.resolve(),
+            cgroup: match value.backend {
+                ResourceBackend::Rlimit => None,
+                ResourceBackend::Cgroup(path) => Some(path),
+            },
+            isolation: value.isolation,
+            cpus: value.cpus,
+            no_aslr: value.no_aslr,
+            nice: value.nice,
+            idle_limit: value.idle_limit,
+            stdin_data: value.stdin_data,
+            capture_stdout: value.capture_stdout,
+            capture_stderr: value.capture_stderr,
+            uid: value.uid,
+            gid: value.gid,
+            groups: value.groups,
+            count_instructions: value.count_instructions,
+            scratch: value.scratch,
         }
     }
 }
@@ -49,7 +183,22 @@ impl SingletonConfig {
             stderr: None,
             exec_path: exec.as_ref().to_string(),
             arguments: Vec::new(),
-            envs: Vec::new(),
+            envs: EnvPolicy::default(),
+            syscalls: SyscallPolicy::default(),
+            backend: ResourceBackend::default(),
+            isolation: None,
+            cpus: Vec::new(),
+            no_aslr: false,
+            nice: None,
+            idle_limit: None,
+            stdin_data: None,
+            capture_stdout: None,
+            capture_stderr: None,
+            uid: None,
+            gid: None,
+            groups: Vec::new(),
+            count_instructions: false,
+            scratch: None,
         }
     }
     /// set the path of input file, which will be rediected to stdin.
@@ -57,6 +206,11 @@ impl SingletonConfig {
         self.stdin = Some(arg.as_ref().to_string());
         self
     }
+    /// feed `data` to stdin from memory instead of a file, which overrides [`SingletonConfig::stdin`].
+    pub fn stdin_bytes(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.stdin_data = Some(data.into());
+        self
+    }
     /// set the path of output file, which will be rediected to stdout.
     pub fn stdout(mut self, arg: impl AsRef<str>) -> Self {
         self.stdout = Some(arg.as_ref().to_string());
@@ -67,6 +221,18 @@ impl SingletonConfig {
         self.stderr = Some(arg.as_ref().to_string());
         self
     }
+    /// capture stdout into [`crate::Termination::stdout`] instead of a file, which overrides
+    /// [`SingletonConfig::stdout`]. Writing more than `cap` bytes results in
+    /// [`crate::Status::OutputLimitExceeded`] and the captured output is truncated to `cap`.
+    pub fn capture_stdout(mut self, cap: Memory) -> Self {
+        self.capture_stdout = Some(cap);
+        self
+    }
+    /// capture stderr into [`crate::Termination::stderr`], see [`SingletonConfig::capture_stdout`].
+    pub fn capture_stderr(mut self, cap: Memory) -> Self {
+        self.capture_stderr = Some(cap);
+        self
+    }
     /// add an argument to the end of argument list
     pub fn push_args<'a>(mut self, args: impl IntoIterator<Item = &'a str>) -> Self {
         for arg in args {
@@ -74,17 +240,31 @@ impl SingletonConfig {
         }
         self
     }
-    /// add an argument to the end of environment list
+    /// set fixed environment variables in `KEY=VALUE` format
     pub fn push_envs<'a>(mut self, args: impl IntoIterator<Item = &'a str>) -> Self {
         for arg in args {
-            self.envs.push(arg.to_string());
+            let (key, value) = arg.split_once('=').unwrap_or((arg, ""));
+            self.envs = self.envs.var(key, value);
         }
         self
     }
-    /// add current process's env to the list
+    /// inherit the given environment variables from the current process (if exist)
+    pub fn inherit_envs<'a>(mut self, keys: impl IntoIterator<Item = &'a str>) -> Self {
+        self.envs = self.envs.inherit(keys);
+        self
+    }
+    /// merge an environment variable policy into the current one
+    pub fn env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.envs = self.envs.merge(policy);
+        self
+    }
+    /// add all of current process's env as fixed variables.
+    ///
+    /// This exposes everything in the environment (e. g. secrets of the server) to the
+    /// executed program, thus it should only be used for debugging. Use [`Self::inherit_envs`] instead.
     pub fn with_current_env(mut self) -> Self {
         for (key, value) in std::env::vars() {
-            self.envs.push(format!("{}={}", key, value));
+            self.envs = self.envs.var(key, value);
         }
         self
     }
@@ -93,6 +273,73 @@ impl SingletonConfig {
         self.limits = modifier(self.limits);
         self
     }
+    /// set the syscall filter policy of the executed program
+    pub fn syscall_policy(mut self, policy: SyscallPolicy) -> Self {
+        self.syscalls = policy;
+        self
+    }
+    /// set how resources are measured and limited
+    pub fn resource_backend(mut self, backend: ResourceBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+    /// run the program in new namespaces with a minimal root directory
+    pub fn isolate(mut self, isolation: Isolation) -> Self {
+        self.isolation = Some(isolation);
+        self
+    }
+    /// pin the program to the given CPUs (linux only), e. g. to give concurrent runs distinct cores
+    pub fn cpus(mut self, cpus: impl IntoIterator<Item = u32>) -> Self {
+        self.cpus = cpus.into_iter().collect();
+        self
+    }
+    /// disable address space layout randomization of the program (linux only)
+    pub fn disable_aslr(mut self) -> Self {
+        self.no_aslr = true;
+        self
+    }
+    /// set the nice value of the program. Negative values require privileges.
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+    /// kill the program with [`crate::Status::IdlenessLimitExceeded`] if its CPU time
+    /// does not grow for `window` (linux only), e. g. when it is blocked on reading an empty stdin
+    pub fn idle_limit(mut self, window: Elapse) -> Self {
+        self.idle_limit = Some(window);
+        self
+    }
+    /// run the program as `uid` and `gid` without supplementary groups (see [`SingletonConfig::groups`]),
+    /// so that it can not access the files of the judger. This requires the judger to run as root.
+    ///
+    /// Note that `RLIMIT_NPROC` counts all processes of the user, thus programs running concurrently
+    /// should use distinct users, or use [`ResourceBackend::Cgroup`] instead.
+    pub fn user(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = Some(uid);
+        self.gid = Some(gid);
+        self
+    }
+    /// set the supplementary groups of the program when switching user
+    pub fn groups(mut self, groups: impl IntoIterator<Item = u32>) -> Self {
+        self.groups = groups.into_iter().collect();
+        self
+    }
+    /// count the user-space instructions executed by the program into [`crate::Termination::instructions`]
+    /// (linux only), which is enabled by [`Limitation::instructions`] as well.
+    /// The count is None if hardware counters are not available, e. g. in most virtual machines.
+    pub fn count_instructions(mut self) -> Self {
+        self.count_instructions = true;
+        self
+    }
+    /// run the program in an empty tmpfs of `size` bytes, which is released after it exits.
+    /// Using up the space (or too many files) results in [`crate::Status::OutputLimitExceeded`].
+    ///
+    /// Mounting requires root, or [`SingletonConfig::isolate`] with which the scratch space
+    /// is the working directory unless [`Isolation::workdir`] is given.
+    pub fn scratch(mut self, size: Memory) -> Self {
+        self.scratch = Some(size);
+        self
+    }
     /// Build the final singleton object
     #[cfg(feature = "exec_sandbox")]
     pub fn build(self) -> super::Singleton {
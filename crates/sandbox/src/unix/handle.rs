@@ -0,0 +1,115 @@
+use super::{
+    sigsafe,
+    singleton::{PipeStdio, Running},
+    Singleton,
+};
+use crate::{Elapse, Memory, Termination};
+use anyhow::Context;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// 程序运行过程中的资源占用
+#[derive(Debug, Clone)]
+pub struct Usage {
+    /// 已经运行的时间
+    pub real_time: Elapse,
+    /// 已经使用的 CPU 时间（仅支持 Linux）
+    pub cpu_time: Elapse,
+    /// 当前的常驻内存（仅支持 Linux）
+    pub memory: Memory,
+}
+
+struct Inner {
+    singleton: Singleton,
+    /// taken by the waiting task once the supervisor exits
+    running: Mutex<Option<Running>>,
+}
+
+/// 异步执行的程序，由 [`Singleton::spawn_async`] 创建
+///
+/// 所有方法都只需要 `&self`，因此可以放在 [`Arc`] 中，在等待的同时从其他任务终止它。
+/// 如果在程序结束前丢弃，程序会继续运行直到结束。
+pub struct SingletonHandle {
+    inner: Arc<Inner>,
+    task: tokio::sync::Mutex<Option<JoinHandle<anyhow::Result<Termination>>>>,
+}
+
+impl Singleton {
+    /// 在后台启动程序，必须在 tokio 运行时中调用
+    pub fn spawn_async(self) -> anyhow::Result<SingletonHandle> {
+        let running = self.spawn(&PipeStdio::default())?;
+        let pid = running.pid();
+        let inner = Arc::new(Inner {
+            singleton: self,
+            running: Mutex::new(Some(running)),
+        });
+        let inner2 = inner.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            // the supervisor is not reaped here, thus it's pid is not reused when being killed
+            sigsafe::wait_exited_nowait(pid).context("wait supervisor")?;
+            let running = inner2
+                .running
+                .lock()
+                .expect("running lock poisoned")
+                .take()
+                .context("supervisor is waited twice")?;
+            Ok(inner2.singleton.wait(running)?.0)
+        });
+        Ok(SingletonHandle {
+            inner,
+            task: tokio::sync::Mutex::new(Some(task)),
+        })
+    }
+}
+
+impl SingletonHandle {
+    /// 等待程序结束，只能成功调用一次。如果程序被 [`SingletonHandle::kill`] 终止，返回错误。
+    pub async fn wait(&self) -> anyhow::Result<Termination> {
+        let mut task = self.task.lock().await;
+        let r = task.as_mut().context("already waited")?.await;
+        *task = None;
+        r.context("join waiting task")?
+    }
+    /// 终止程序，已经结束的程序会被忽略
+    pub fn kill(&self) -> anyhow::Result<()> {
+        let running = self.inner.running.lock().expect("running lock poisoned");
+        if let Some(running) = running.as_ref() {
+            running.cancel().context("signal supervisor")?;
+        }
+        Ok(())
+    }
+    /// 程序当前的资源占用，程序已经结束时返回 None
+    pub fn usage(&self) -> Option<Usage> {
+        let running = self.inner.running.lock().expect("running lock poisoned");
+        let running = running.as_ref()?;
+        let real_time = (sigsafe::monotonic_ns() - running.start()).max(0) as u64 / 1_000_000;
+        let mut usage = Usage {
+            real_time: Elapse::from(real_time),
+            cpu_time: Elapse::from(0),
+            memory: Memory::from(0),
+        };
+        if let Some(pid) = running.tested_pid() {
+            if let Ok(ns) = sigsafe::process_cputime_ns(pid) {
+                usage.cpu_time = Elapse::from(ns as u64 / 1_000_000);
+            }
+            if let Some(rss) = resident_memory(pid) {
+                usage.memory = rss;
+            }
+        }
+        Some(usage)
+    }
+}
+
+/// VmRSS of the process (linux only)
+fn resident_memory(pid: i32) -> Option<Memory> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let kb: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Memory::from(kb * 1024))
+}
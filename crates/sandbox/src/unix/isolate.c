@@ -0,0 +1,218 @@
+#define _GNU_SOURCE
+#include "utils.h"
+
+#include <string.h>
+
+#ifdef __linux__
+
+#include <fcntl.h>
+#include <limits.h>
+#include <linux/capability.h>
+#include <sched.h>
+#include <sys/mount.h>
+#include <sys/prctl.h>
+#include <sys/stat.h>
+#include <sys/statfs.h>
+#include <sys/syscall.h>
+
+/* write a string to a file, used for uid_map, gid_map and setgroups */
+static int write_file(const char *path, const char *content) {
+  int fd = open(path, O_WRONLY | O_CLOEXEC);
+  if (fd < 0) return -1;
+  size_t len = strlen(content);
+  ssize_t r = write(fd, content, len);
+  int saved = errno;
+  close(fd);
+  errno = saved;
+  return r == (ssize_t)len ? 0 : -1;
+}
+
+/* format "0 <id> 1" without calling snprintf */
+static void id_map(char *buf, unsigned int id) {
+  char digits[16];
+  int n = 0;
+  do {
+    digits[n++] = '0' + id % 10;
+    id /= 10;
+  } while (id);
+  *buf++ = '0';
+  *buf++ = ' ';
+  while (n) *buf++ = digits[--n];
+  *buf++ = ' ';
+  *buf++ = '1';
+  *buf = 0;
+}
+
+int enter_user_pid_ns(int switch_user) {
+  uid_t uid = geteuid();
+  gid_t gid = getegid();
+  if (!(switch_user && uid == 0) && unshare(CLONE_NEWUSER) == 0) {
+    char buf[32];
+    // map the current user to root in the new user namespace
+    if (write_file("/proc/self/setgroups", "deny") < 0) return -1;
+    id_map(buf, uid);
+    if (write_file("/proc/self/uid_map", buf) < 0) return -1;
+    id_map(buf, gid);
+    if (write_file("/proc/self/gid_map", buf) < 0) return -1;
+  } else if (uid != 0) {
+    // unprivileged users can not create other namespaces without a user namespace
+    return -1;
+  }
+  return unshare(CLONE_NEWPID);
+}
+
+/* concatenate root and path into buf */
+static int join_path(char *buf, const char *root, const char *path) {
+  size_t lr = strlen(root), lp = strlen(path);
+  if (lr + lp + 1 > PATH_MAX) {
+    errno = ENAMETOOLONG;
+    return -1;
+  }
+  memcpy(buf, root, lr);
+  memcpy(buf + lr, path, lp + 1);
+  return 0;
+}
+
+/* mkdir -p, where the prefix of length skip is known to exist */
+static int mkdir_all(char *path, size_t skip) {
+  for (char *p = path + skip + 1; *p; p++) {
+    if (*p != '/') continue;
+    *p = 0;
+    int r = mkdir(path, 0755);
+    *p = '/';
+    if (r < 0 && errno != EEXIST) return -1;
+  }
+  if (mkdir(path, 0755) < 0 && errno != EEXIST) return -1;
+  return 0;
+}
+
+/* bind mount path to the same path under root, symbolic links are copied */
+static int bind_path(const char *root, const bind_mount_t *b) {
+  char target[PATH_MAX];
+  struct stat st;
+  if (lstat(b->path, &st) < 0) {
+    // nonexistent paths are ignored
+    return errno == ENOENT ? 0 : -1;
+  }
+  if (join_path(target, root, b->path) < 0) return -1;
+
+  // create parent directories
+  char *slash = strrchr(target, '/');
+  if (slash > target + strlen(root)) {
+    *slash = 0;
+    int r = mkdir_all(target, strlen(root));
+    *slash = '/';
+    if (r < 0) return -1;
+  }
+
+  if (S_ISLNK(st.st_mode)) {
+    char link[PATH_MAX];
+    ssize_t len = readlink(b->path, link, sizeof(link) - 1);
+    if (len < 0) return -1;
+    link[len] = 0;
+    return symlink(link, target) < 0 && errno != EEXIST ? -1 : 0;
+  }
+  if (S_ISDIR(st.st_mode)) {
+    if (mkdir(target, 0755) < 0 && errno != EEXIST) return -1;
+  } else {
+    int fd = open(target, O_WRONLY | O_CREAT | O_CLOEXEC, 0644);
+    if (fd < 0) return -1;
+    close(fd);
+  }
+  if (mount(b->path, target, NULL, MS_BIND, NULL) < 0) return -1;
+  if (!b->writable) {
+    // flags locked by the original mount (e. g. nosuid) must be preserved
+    struct statfs sf;
+    if (statfs(target, &sf) < 0) return -1;
+    unsigned long flags =
+        sf.f_flags & (MS_NOSUID | MS_NODEV | MS_NOEXEC | MS_NOATIME |
+                      MS_NODIRATIME | MS_RELATIME);
+    if (mount(NULL, target, NULL, MS_BIND | MS_REMOUNT | MS_RDONLY | flags,
+              NULL) < 0)
+      return -1;
+  }
+  return 0;
+}
+
+/* make sure the program executed later has no capability, even if it runs as
+ * root in the user namespace, otherwise it could remount the read-only binds.
+ * The effective and permitted sets are kept for switching user. */
+static int drop_capabilities(void) {
+  for (int cap = 0; prctl(PR_CAPBSET_READ, cap, 0, 0, 0) >= 0; cap++) {
+    if (prctl(PR_CAPBSET_DROP, cap, 0, 0, 0) < 0) return -1;
+  }
+  // ambient capabilities are not supported before linux 4.3
+  if (prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_CLEAR_ALL, 0, 0, 0) < 0 &&
+      errno != EINVAL)
+    return -1;
+  struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+  struct __user_cap_data_struct data[_LINUX_CAPABILITY_U32S_3];
+  if (syscall(SYS_capget, &hdr, data) < 0) return -1;
+  for (int i = 0; i < _LINUX_CAPABILITY_U32S_3; i++) data[i].inheritable = 0;
+  if (syscall(SYS_capset, &hdr, data) < 0) return -1;
+  return 0;
+}
+
+int setup_isolated_root(const char *root, const bind_mount_t *binds, int len,
+                        const char *workdir) {
+  if (unshare(CLONE_NEWNS | CLONE_NEWNET | CLONE_NEWIPC | CLONE_NEWUTS) < 0)
+    return -1;
+  if (sethostname("zroj-sandbox", strlen("zroj-sandbox")) < 0) return -1;
+  // do not propagate mount events to the original namespace
+  if (mount(NULL, "/", NULL, MS_REC | MS_PRIVATE, NULL) < 0) return -1;
+  if (mount("tmpfs", root, "tmpfs", MS_NOSUID, "mode=0755") < 0) return -1;
+  for (int i = 0; i < len; i++) {
+    if (bind_path(root, binds + i) < 0) return -1;
+  }
+  if (chdir(root) < 0) return -1;
+  // stack the old root on the new one, then detach it
+  if (syscall(SYS_pivot_root, ".", ".") < 0) return -1;
+  if (umount2(".", MNT_DETACH) < 0) return -1;
+  if (mount(NULL, "/", NULL, MS_BIND | MS_REMOUNT | MS_RDONLY | MS_NOSUID,
+            NULL) < 0)
+    return -1;
+  if (drop_capabilities() < 0) return -1;
+  return chdir(workdir ? workdir : "/");
+}
+
+int mount_scratch(const char *dir, const char *options) {
+  // the scratch space is only visible in a private mount namespace, thus it is
+  // released once the sandbox exits
+  if (unshare(CLONE_NEWNS) < 0) return -1;
+  if (mount(NULL, "/", NULL, MS_REC | MS_PRIVATE, NULL) < 0) return -1;
+  if (mount("tmpfs", dir, "tmpfs", MS_NOSUID | MS_NODEV, options) < 0)
+    return -1;
+  return chdir(dir);
+}
+
+int scratch_full(const char *dir) {
+  struct statfs sf;
+  if (statfs(dir, &sf) < 0) return -1;
+  return sf.f_bavail == 0 || sf.f_ffree == 0;
+}
+
+#else
+
+int enter_user_pid_ns(int switch_user) {
+  (void)switch_user;
+  errno = ENOSYS;
+  return -1;
+}
+
+int setup_isolated_root(const char *root, const bind_mount_t *binds, int len,
+                        const char *workdir) {
+  errno = ENOSYS;
+  return -1;
+}
+
+int mount_scratch(const char *dir, const char *options) {
+  errno = ENOSYS;
+  return -1;
+}
+
+int scratch_full(const char *dir) {
+  errno = ENOSYS;
+  return -1;
+}
+
+#endif
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+/// 运行程序所需的系统目录，不存在的路径会被忽略
+const SYSTEM_PATHS: &[&str] = &[
+    "/bin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/usr",
+    "/etc/alternatives",
+    "/etc/ld.so.cache",
+];
+
+/// 常用的设备文件
+const DEVICE_PATHS: &[&str] = &["/dev/null", "/dev/zero", "/dev/random", "/dev/urandom"];
+
+/// 使用 Linux namespace 隔离被测程序（仅支持 Linux）
+///
+/// 被测程序会运行在新的 PID、网络、IPC、UTS 和 mount namespace 中（如果可以的话也会使用 user namespace，
+/// 非 root 用户必须使用 user namespace），其根目录是一个只读的 tmpfs，只包含挂载进来的路径，
+/// 并且路径与原来的保持一致。
+///
+/// 标准输入输出在切换根目录之前打开，因此不需要挂载它们所在的目录。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Isolation {
+    readonly: Vec<String>,
+    writable: Vec<String>,
+    workdir: Option<String>,
+}
+
+impl Default for Isolation {
+    /// 只读挂载常用的系统目录，可写挂载常用的设备文件
+    fn default() -> Self {
+        Self {
+            readonly: SYSTEM_PATHS.iter().map(|s| s.to_string()).collect(),
+            writable: DEVICE_PATHS.iter().map(|s| s.to_string()).collect(),
+            workdir: None,
+        }
+    }
+}
+
+impl Isolation {
+    /// 不挂载任何路径
+    pub fn empty() -> Self {
+        Self {
+            readonly: Vec::new(),
+            writable: Vec::new(),
+            workdir: None,
+        }
+    }
+    /// 以只读方式挂载路径（目录或文件），必须是绝对路径
+    pub fn readonly(mut self, path: impl AsRef<str>) -> Self {
+        self.readonly.push(path.as_ref().to_string());
+        self
+    }
+    /// 以可写方式挂载路径（目录或文件），必须是绝对路径
+    pub fn writable(mut self, path: impl AsRef<str>) -> Self {
+        self.writable.push(path.as_ref().to_string());
+        self
+    }
+    /// 被测程序的工作目录，默认为根目录
+    pub fn workdir(mut self, path: impl AsRef<str>) -> Self {
+        self.workdir = Some(path.as_ref().to_string());
+        self
+    }
+    /// 可写挂载临时空间，未指定工作目录时将其作为工作目录
+    #[cfg(feature = "exec_sandbox")]
+    pub(crate) fn with_scratch(&self, dir: &str) -> Self {
+        let mut r = self.clone().writable(dir);
+        r.workdir.get_or_insert_with(|| dir.to_string());
+        r
+    }
+}
+
+#[cfg(feature = "exec_sandbox")]
+pub(crate) use imp::{PreparedRoot, PreparedScratch};
+
+#[cfg(feature = "exec_sandbox")]
+mod imp {
+    use super::Isolation;
+    use crate::{
+        unix::sigsafe::{self, BindMount},
+        Memory,
+    };
+    use std::ffi::CString;
+
+    /// 在 fork 之前准备好切换根目录所需的数据，避免在子进程中分配内存
+    pub struct PreparedRoot {
+        // an empty directory to mount the new root on, removed on drop
+        _root: tempfile::TempDir,
+        root_c: CString,
+        // referenced by binds
+        _paths: Vec<CString>,
+        binds: Vec<BindMount>,
+        workdir: Option<CString>,
+    }
+
+    // the pointers in `binds` point to `_paths`, which is owned and moved together
+    unsafe impl Send for PreparedRoot {}
+
+    impl PreparedRoot {
+        pub fn new(isolation: &Isolation) -> anyhow::Result<Self> {
+            let root = tempfile::tempdir()?;
+            let root_c = CString::new(root.path().to_str().unwrap_or_default())?;
+            let mut paths = Vec::new();
+            let mut writable = Vec::new();
+            for (list, w) in [(&isolation.readonly, false), (&isolation.writable, true)] {
+                for path in list {
+                    if !path.starts_with('/') {
+                        anyhow::bail!("mount path {path} is not absolute");
+                    }
+                    paths.push(CString::new(path.as_str())?);
+                    writable.push(w);
+                }
+            }
+            let binds = paths
+                .iter()
+                .zip(writable)
+                .map(|(p, w)| BindMount {
+                    path: p.as_ptr(),
+                    writable: w as i32,
+                })
+                .collect();
+            Ok(Self {
+                _root: root,
+                root_c,
+                _paths: paths,
+                binds,
+                workdir: isolation
+                    .workdir
+                    .as_ref()
+                    .map(|s| CString::new(s.as_str()))
+                    .transpose()?,
+            })
+        }
+        /// 在子进程中切换根目录，需要保证 async-signal-safe
+        pub fn enter(&self) -> Result<(), sigsafe::Errno> {
+            sigsafe::setup_isolated_root(&self.root_c, &self.binds, self.workdir.as_deref())
+        }
+    }
+
+    /// 临时空间最多包含的文件（目录）数
+    const SCRATCH_INODES: u64 = 1024;
+
+    /// 在 fork 之前准备好挂载临时空间所需的数据，见 [`crate::unix::SingletonConfig::scratch`]
+    pub struct PreparedScratch {
+        // the mount point, which is empty outside the sandbox and removed on drop
+        dir: tempfile::TempDir,
+        dir_c: CString,
+        options: CString,
+    }
+
+    impl PreparedScratch {
+        pub fn new(size: Memory) -> anyhow::Result<Self> {
+            let dir = tempfile::tempdir()?;
+            let dir_c = CString::new(dir.path().to_str().unwrap_or_default())?;
+            let options = CString::new(format!("size={},nr_inodes={SCRATCH_INODES}", size.byte()))?;
+            Ok(Self {
+                dir,
+                dir_c,
+                options,
+            })
+        }
+        pub fn path(&self) -> &str {
+            self.dir.path().to_str().unwrap_or_default()
+        }
+        /// 在子进程中挂载临时空间并进入，需要保证 async-signal-safe
+        pub fn mount(&self) -> Result<(), sigsafe::Errno> {
+            sigsafe::mount_scratch(&self.dir_c, &self.options)
+        }
+        /// 临时空间是否已经用完，需要在挂载的进程中调用
+        pub fn full(&self) -> bool {
+            sigsafe::scratch_full(&self.dir_c)
+        }
+    }
+}
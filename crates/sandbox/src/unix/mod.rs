@@ -4,16 +4,30 @@ pub(crate) mod share_mem;
 #[cfg(feature = "exec_sandbox")]
 pub(crate) mod sigsafe;
 
+pub(crate) mod cgroup;
 pub(crate) mod config;
+pub(crate) mod isolation;
+pub(crate) mod seccomp;
 
+#[cfg(feature = "async")]
+pub(crate) mod handle;
+#[cfg(feature = "exec_sandbox")]
+pub(crate) mod pipeline;
 #[cfg(feature = "exec_sandbox")]
 pub(crate) mod singleton;
 
 use std::fmt::Display;
 use std::str::FromStr;
 
-pub use config::SingletonConfig;
+pub use cgroup::ResourceBackend;
+pub use config::{EnvPolicy, SingletonConfig};
+pub use isolation::Isolation;
+pub use seccomp::SyscallPolicy;
 
+#[cfg(feature = "async")]
+pub use handle::{SingletonHandle, Usage};
+#[cfg(feature = "exec_sandbox")]
+pub use pipeline::{Pipeline, PipelineTermination};
 #[cfg(feature = "exec_sandbox")]
 pub use singleton::Singleton;
 
@@ -21,9 +35,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::Elapse;
 use crate::Memory;
+use crate::ParseLimitError;
 
 /// 对资源 T 类型的限制
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Lim<T>
 where
     T: PartialOrd,
@@ -47,6 +62,17 @@ impl<T: PartialOrd + Copy> Lim<T> {
         }
     }
 }
+impl<T: PartialOrd + Display> Lim<T> {
+    /// 检查 soft limit 不超过 hard limit
+    pub fn validate(&self) -> Result<(), ParseLimitError> {
+        match self {
+            Lim::Double(s, h) if s > h => Err(ParseLimitError::new(format!(
+                "soft limit {s} is greater than hard limit {h}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
 impl<T: PartialOrd + Display> Display for Lim<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -55,23 +81,28 @@ impl<T: PartialOrd + Display> Display for Lim<T> {
         }
     }
 }
-impl<T: PartialOrd + FromStr> FromStr for Lim<T> {
-    type Err = &'static str;
+/// 格式为 `limit`、`limit,-` 或者 `soft,hard`
+impl<T: PartialOrd + Display + FromStr> FromStr for Lim<T>
+where
+    T::Err: Display,
+{
+    type Err = ParseLimitError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |v: &str| {
+            v.trim()
+                .parse()
+                .map_err(|e| ParseLimitError::new(format!("invalid limit {v:?}: {e}")))
+        };
         let a: Vec<&str> = s.split(',').collect();
-        match a.as_slice() {
-            [s, h] => {
-                let Ok(s) = s.parse() else {
-                    return Err("soft limit not parsed");
-                };
-                let Ok(h) = h.parse() else {
-                    return Ok(Self::Single(s));
-                };
-                Ok(Self::Double(s, h))
-            }
-            _ => Err("invalid str"),
-        }
+        let lim = match a.as_slice() {
+            [l] => Self::Single(parse(l)?),
+            [l, h] if h.trim() == "-" => Self::Single(parse(l)?),
+            [s, h] => Self::Double(parse(s)?, parse(h)?),
+            _ => return Err(ParseLimitError::new(format!("invalid limit {s:?}"))),
+        };
+        lim.validate()?;
+        Ok(lim)
     }
 }
 impl<T: PartialOrd + Copy> From<T> for Lim<T> {
@@ -81,7 +112,7 @@ impl<T: PartialOrd + Copy> From<T> for Lim<T> {
 }
 
 /// 对进程施加各种类型的资源限制
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Limitation {
     /// 限制实际运行时间，linux 上一般是用来做一个大保底
     ///
@@ -106,45 +137,169 @@ pub struct Limitation {
     ///
     /// soft limit 和 hard limit，一般以 soft 为衡量标准
     pub fileno: Lim<u64>,
+    /// 限制同时存在的进程（线程）数，用于防止 fork 炸弹，以 hard limit 为准
+    ///
+    /// 使用 cgroup 时通过 `pids.max` 限制，只统计被测程序及其子进程。
+    /// 否则只有切换到其他用户（见 [`SingletonConfig::user`]）时才通过 `RLIMIT_NPROC` 限制，
+    /// 它统计的是该用户的所有进程，因此需要为沙盒使用专门的用户；两者都不满足时不做限制。
+    ///
+    /// 超出限制时报告 [`crate::Status::ProcessLimitExceeded`]。`RLIMIT_NPROC` 会多允许一个进程，
+    /// 沙盒定期统计该用户的线程数，发现超出后终止被测程序所在的进程组。
+    #[serde(default = "default_nproc")]
+    pub nproc: Lim<u64>,
+    /// 限制被测程序在用户态执行的指令数，超出时视为超时。与 CPU 时间相比不受主机负载和 CPU 频率的影响。
+    ///
+    /// 程序结束后才检查，因此仍然需要 CPU 时间限制来终止死循环（可以适当放宽）。
+    /// 需要 `perf_event_open` 支持硬件计数器（仅支持 Linux），不支持时忽略该限制，仅以 CPU 时间为准。
+    #[serde(default)]
+    pub instructions: Option<u64>,
+}
+
+impl Limitation {
+    /// 检查每一项限制的 soft limit 不超过 hard limit
+    pub fn validate(&self) -> Result<(), ParseLimitError> {
+        let check = |key: &str, r: Result<(), ParseLimitError>| {
+            r.map_err(|e| ParseLimitError::new(format!("{key}: {e}")))
+        };
+        check("real", self.real_time.validate())?;
+        check("cpu", self.cpu_time.validate())?;
+        check("vmem", self.virtual_memory.validate())?;
+        check("mem", self.real_memory.validate())?;
+        check("stack", self.stack_memory.validate())?;
+        check("output", self.output_memory.validate())?;
+        check("fileno", self.fileno.validate())?;
+        check("nproc", self.nproc.validate())
+    }
+    /// 解析 `key=value` 格式，未给出的限制使用默认值
+    fn from_keyed(s: &str) -> Result<Self, ParseLimitError> {
+        fn parse<T>(key: &str, v: &str) -> Result<Lim<T>, ParseLimitError>
+        where
+            T: PartialOrd + Display + FromStr,
+            T::Err: Display,
+        {
+            v.parse()
+                .map_err(|e| ParseLimitError::new(format!("{key}: {e}")))
+        }
+
+        // a value without key is the hard limit of the previous one, e. g. `cpu=1s,2s`
+        let mut items: Vec<(&str, String)> = Vec::new();
+        for token in s.split(',') {
+            match token.split_once('=') {
+                Some((k, v)) => items.push((k.trim(), v.to_string())),
+                None => match items.last_mut() {
+                    Some((_, v)) => {
+                        v.push(',');
+                        v.push_str(token);
+                    }
+                    None => return Err(ParseLimitError::new(format!("missing key for {token:?}"))),
+                },
+            }
+        }
+        let mut r = Self::default();
+        let mut seen = Vec::new();
+        for (k, v) in items {
+            // aliases of the same limit are also duplicated keys
+            let key = match k {
+                "real_time" => "real",
+                "cpu_time" => "cpu",
+                "virtual_memory" => "vmem",
+                "real_memory" => "mem",
+                "stack_memory" => "stack",
+                "output_memory" => "output",
+                "instructions" => "inst",
+                k => k,
+            };
+            if seen.contains(&key) {
+                return Err(ParseLimitError::new(format!("duplicated limit {k:?}")));
+            }
+            seen.push(key);
+            match key {
+                "real" => r.real_time = parse(k, &v)?,
+                "cpu" => r.cpu_time = parse(k, &v)?,
+                "vmem" => r.virtual_memory = parse(k, &v)?,
+                "mem" => r.real_memory = parse(k, &v)?,
+                "stack" => r.stack_memory = parse(k, &v)?,
+                "output" => r.output_memory = parse(k, &v)?,
+                "fileno" => r.fileno = parse(k, &v)?,
+                "nproc" => r.nproc = parse(k, &v)?,
+                "inst" => {
+                    r.instructions = Some(
+                        v.trim()
+                            .parse()
+                            .map_err(|e| ParseLimitError::new(format!("{k}: {e}")))?,
+                    )
+                }
+                _ => return Err(ParseLimitError::new(format!("unknown limit {k:?}"))),
+            }
+        }
+        Ok(r)
+    }
 }
 
+/// 输出 `key=value` 格式
 impl Display for Limitation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}:{}:{}:{}:{}:{}:{}",
+            "real={},cpu={},vmem={},mem={},stack={},output={},fileno={},nproc={}",
             self.real_time,
             self.cpu_time,
             self.virtual_memory,
             self.real_memory,
             self.stack_memory,
             self.output_memory,
-            self.fileno
-        )
+            self.fileno,
+            self.nproc
+        )?;
+        if let Some(inst) = self.instructions {
+            write!(f, ",inst={inst}")?;
+        }
+        Ok(())
     }
 }
 
+/// 支持两种格式：
+///
+/// - `key=value` 的列表，例如 `cpu=1s,mem=256MiB,real=1s,2s`，未给出的限制使用默认值。
+///   key 可以是 `real`、`cpu`、`vmem`、`mem`、`stack`、`output`、`fileno`、`nproc`、`inst`
+///   （也可以使用字段名）；
+/// - 按字段顺序用冒号分隔的八个限制（不包括指令数）。
+///
+/// 每个限制的格式见 [`Lim`] 的 [`FromStr`]，时间和内存可以带单位（见 [`Elapse`] 和 [`Memory`]）。
 impl FromStr for Limitation {
-    type Err = &'static str;
+    type Err = ParseLimitError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let r: Vec<&str> = s.split(':').collect();
-        match r.as_slice() {
-            [rt, ct, vm, rm, sm, om, fo] => Ok(Self {
-                real_time: rt.parse()?,
-                cpu_time: ct.parse()?,
-                virtual_memory: vm.parse()?,
-                real_memory: rm.parse()?,
-                stack_memory: sm.parse()?,
-                output_memory: om.parse()?,
-                fileno: fo.parse()?,
-            }),
-            _ => Err("invalid limitation format"),
-        }
+        let r = if s.contains('=') {
+            Self::from_keyed(s)?
+        } else {
+            let r: Vec<&str> = s.split(':').collect();
+            match r.as_slice() {
+                [rt, ct, vm, rm, sm, om, fo, np] => Self {
+                    real_time: rt.parse()?,
+                    cpu_time: ct.parse()?,
+                    virtual_memory: vm.parse()?,
+                    real_memory: rm.parse()?,
+                    stack_memory: sm.parse()?,
+                    output_memory: om.parse()?,
+                    fileno: fo.parse()?,
+                    nproc: np.parse()?,
+                    instructions: None,
+                },
+                _ => return Err(ParseLimitError::new("invalid limitation format")),
+            }
+        };
+        r.validate()?;
+        Ok(r)
     }
 }
 
-/// 考虑安全性的默认限制，简单来说时间限制 1 分钟，空间限制 1 GB，最多同时打开 100 个文件
+/// 考虑安全性的默认限制，简单来说时间限制 1 分钟，空间限制 1 GB，最多同时打开 100 个文件，
+/// 最多同时存在 64 个进程
+fn default_nproc() -> Lim<u64> {
+    Limitation::default().nproc
+}
+
 impl Default for Limitation {
     fn default() -> Self {
         Self {
@@ -155,6 +310,8 @@ impl Default for Limitation {
             stack_memory: Lim::Single((1 << 30).into()),
             output_memory: Lim::Single((1 << 30).into()),
             fileno: Lim::Single(100),
+            nproc: Lim::Single(64),
+            instructions: None,
         }
     }
 }
@@ -169,4 +326,54 @@ mod tests {
         let d2: Limitation = d.to_string().parse().unwrap();
         dbg!(d.to_string(), d2);
     }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("1.5s".parse::<Elapse>().unwrap().ms(), 1500);
+        assert_eq!("200ms".parse::<Elapse>().unwrap().ms(), 200);
+        assert_eq!("300".parse::<Elapse>().unwrap().ms(), 300);
+        assert_eq!("256MiB".parse::<Memory>().unwrap().byte(), 256 << 20);
+        assert_eq!("64M".parse::<Memory>().unwrap().byte(), 64 << 20);
+        assert_eq!("1.5k".parse::<Memory>().unwrap().byte(), 1536);
+        assert!("1.5h".parse::<Elapse>().is_err());
+        assert!("-1".parse::<Memory>().is_err());
+        assert!("99999999999999999999999s".parse::<Elapse>().is_err());
+        assert!("99999999999999999999.5".parse::<Memory>().is_err());
+        assert!("18446744073709551615.0".parse::<Memory>().is_err());
+        assert!("17179869184.5G".parse::<Memory>().is_err());
+
+        let l: Limitation = "cpu=1s,mem=256MiB,real=1s,2s,nproc=1".parse().unwrap();
+        assert!(matches!(l.cpu_time, Lim::Single(t) if t.ms() == 1000));
+        assert!(matches!(l.real_memory, Lim::Single(m) if m.byte() == 256 << 20));
+        assert!(matches!(l.real_time, Lim::Double(s, h) if s.ms() == 1000 && h.ms() == 2000));
+        assert!(matches!(l.nproc, Lim::Single(1)));
+        assert_eq!(
+            l.fileno.to_string(),
+            Limitation::default().fileno.to_string()
+        );
+        assert_eq!(l.instructions, None);
+
+        let l: Limitation = "cpu=2s,inst=1000000".parse().unwrap();
+        assert_eq!(l.instructions, Some(1000000));
+        let l: Limitation = l.to_string().parse().unwrap();
+        assert_eq!(l.instructions, Some(1000000));
+
+        assert!("cpu=2s,1s".parse::<Limitation>().is_err());
+        assert!("time=1s".parse::<Limitation>().is_err());
+        assert!("cpu=1s,cpu=2s".parse::<Limitation>().is_err());
+        assert!("cpu=1s,cpu_time=2s".parse::<Limitation>().is_err());
+        assert!("1s,2s:1s:1G:1G:1G:1G:10:1".parse::<Limitation>().is_ok());
+        assert!("1s:1s:1G:1G:1G:1G:10".parse::<Limitation>().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_old_format() {
+        // serialized before nproc and instructions were added
+        let mut v = serde_json::to_value(Limitation::default()).unwrap();
+        let obj = v.as_object_mut().unwrap();
+        obj.remove("nproc");
+        obj.remove("instructions");
+        let l: Limitation = serde_json::from_value(v).unwrap();
+        assert_eq!(l.nproc.to_string(), Limitation::default().nproc.to_string());
+    }
 }
@@ -0,0 +1,121 @@
+use super::{
+    sigsafe,
+    singleton::{PipeStdio, Running},
+    Singleton,
+};
+use crate::Termination;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// 同时执行多个程序，并用管道连接它们的标准输入输出，例如交互题中的选手程序和交互器。
+///
+/// 每个程序有各自的资源限制。被管道连接的标准输入（输出）会忽略 [`Singleton`] 中设置的文件。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pipeline {
+    processes: Vec<Singleton>,
+    pipes: Vec<(usize, usize)>,
+}
+
+/// 多个程序的执行结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PipelineTermination {
+    /// 各个程序的终止信息，顺序与添加程序的顺序相同
+    pub terminations: Vec<Termination>,
+    /// 最先结束的程序的下标
+    pub first: usize,
+}
+
+impl Pipeline {
+    /// 创建一组程序，程序的下标即为其在 `processes` 中的下标
+    pub fn new(processes: Vec<Singleton>) -> Self {
+        Self {
+            processes,
+            pipes: Vec::new(),
+        }
+    }
+    /// 将第 `from` 个程序的标准输出连接到第 `to` 个程序的标准输入
+    pub fn pipe(mut self, from: usize, to: usize) -> Self {
+        self.pipes.push((from, to));
+        self
+    }
+    fn check(&self) -> anyhow::Result<()> {
+        let n = self.processes.len();
+        if n == 0 {
+            bail!("empty pipeline");
+        }
+        let mut stdin_used = vec![false; n];
+        let mut stdout_used = vec![false; n];
+        for &(from, to) in &self.pipes {
+            if from >= n || to >= n {
+                bail!("invalid pipe ({from}, {to}) for {n} processes");
+            }
+            if std::mem::replace(&mut stdout_used[from], true) {
+                bail!("stdout of process {from} is connected more than once");
+            }
+            if std::mem::replace(&mut stdin_used[to], true) {
+                bail!("stdin of process {to} is connected more than once");
+            }
+        }
+        Ok(())
+    }
+    /// 执行所有程序，等待它们全部结束
+    pub fn exec_pipeline(&self) -> anyhow::Result<PipelineTermination> {
+        self.check()?;
+
+        let mut stdio: Vec<PipeStdio> = self.processes.iter().map(|_| Default::default()).collect();
+        let mut all = Vec::new();
+        let mut r = Ok(());
+        for &(from, to) in &self.pipes {
+            match sigsafe::pipe() {
+                Ok((read, write)) => {
+                    stdio[from].stdout = Some(write);
+                    stdio[to].stdin = Some(read);
+                    all.extend([read, write]);
+                }
+                Err(e) => {
+                    r = Err(e).context("create pipe");
+                    break;
+                }
+            }
+        }
+        for s in &mut stdio {
+            s.all.clone_from(&all);
+        }
+
+        let mut running: Vec<Running> = Vec::new();
+        if r.is_ok() {
+            for (p, s) in self.processes.iter().zip(&stdio) {
+                match p.spawn(s) {
+                    Ok(h) => running.push(h),
+                    Err(e) => {
+                        r = Err(e);
+                        break;
+                    }
+                }
+            }
+        }
+        // the pipe ends are held by the children now
+        all.into_iter().for_each(sigsafe::close);
+
+        // the spawned processes exit at last, even if the others failed to spawn
+        let results: Vec<_> = self
+            .processes
+            .iter()
+            .zip(running)
+            .map(|(p, h)| p.wait(h))
+            .collect();
+        r?;
+
+        let mut terminations = Vec::new();
+        let mut first = (i64::MAX, 0);
+        for (i, res) in results.into_iter().enumerate() {
+            let (term, exit_time) = res?;
+            first = first.min((exit_time, i));
+            terminations.push(term);
+        }
+        Ok(PipelineTermination {
+            terminations,
+            first: first.1,
+        })
+    }
+}
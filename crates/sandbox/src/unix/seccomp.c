@@ -0,0 +1,581 @@
+#define _GNU_SOURCE
+#include "utils.h"
+
+#include <string.h>
+
+#ifdef __linux__
+
+#include <fcntl.h>
+#include <linux/audit.h>
+#include <linux/filter.h>
+#include <linux/seccomp.h>
+#include <poll.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/ioctl.h>
+#include <sys/prctl.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+
+#if defined(__x86_64__)
+#define SANDBOX_AUDIT_ARCH AUDIT_ARCH_X86_64
+#elif defined(__aarch64__)
+#define SANDBOX_AUDIT_ARCH AUDIT_ARCH_AARCH64
+#endif
+
+#if __BYTE_ORDER__ == __ORDER_LITTLE_ENDIAN__
+#define ARG_LO(i) (offsetof(struct seccomp_data, args[i]))
+#define ARG_HI(i) (offsetof(struct seccomp_data, args[i]) + 4)
+#else
+#define ARG_LO(i) (offsetof(struct seccomp_data, args[i]) + 4)
+#define ARG_HI(i) (offsetof(struct seccomp_data, args[i]))
+#endif
+
+/* syscall names that can appear in an allow list */
+static const struct {
+  const char *name;
+  int nr;
+} syscall_table[] = {
+#define ENTRY(name) {#name, SYS_##name},
+#ifdef SYS_access
+    ENTRY(access)
+#endif
+#ifdef SYS_alarm
+    ENTRY(alarm)
+#endif
+#ifdef SYS_arch_prctl
+    ENTRY(arch_prctl)
+#endif
+#ifdef SYS_brk
+    ENTRY(brk)
+#endif
+#ifdef SYS_chdir
+    ENTRY(chdir)
+#endif
+#ifdef SYS_chmod
+    ENTRY(chmod)
+#endif
+#ifdef SYS_clock_getres
+    ENTRY(clock_getres)
+#endif
+#ifdef SYS_clock_gettime
+    ENTRY(clock_gettime)
+#endif
+#ifdef SYS_clock_nanosleep
+    ENTRY(clock_nanosleep)
+#endif
+#ifdef SYS_clone
+    ENTRY(clone)
+#endif
+#ifdef SYS_clone3
+    ENTRY(clone3)
+#endif
+#ifdef SYS_close
+    ENTRY(close)
+#endif
+#ifdef SYS_close_range
+    ENTRY(close_range)
+#endif
+#ifdef SYS_connect
+    ENTRY(connect)
+#endif
+#ifdef SYS_dup
+    ENTRY(dup)
+#endif
+#ifdef SYS_dup2
+    ENTRY(dup2)
+#endif
+#ifdef SYS_dup3
+    ENTRY(dup3)
+#endif
+#ifdef SYS_epoll_create1
+    ENTRY(epoll_create1)
+#endif
+#ifdef SYS_epoll_ctl
+    ENTRY(epoll_ctl)
+#endif
+#ifdef SYS_epoll_wait
+    ENTRY(epoll_wait)
+#endif
+#ifdef SYS_epoll_pwait
+    ENTRY(epoll_pwait)
+#endif
+#ifdef SYS_eventfd2
+    ENTRY(eventfd2)
+#endif
+#ifdef SYS_execve
+    ENTRY(execve)
+#endif
+#ifdef SYS_exit
+    ENTRY(exit)
+#endif
+#ifdef SYS_exit_group
+    ENTRY(exit_group)
+#endif
+#ifdef SYS_faccessat
+    ENTRY(faccessat)
+#endif
+#ifdef SYS_faccessat2
+    ENTRY(faccessat2)
+#endif
+#ifdef SYS_fadvise64
+    ENTRY(fadvise64)
+#endif
+#ifdef SYS_fchdir
+    ENTRY(fchdir)
+#endif
+#ifdef SYS_fchmod
+    ENTRY(fchmod)
+#endif
+#ifdef SYS_fcntl
+    ENTRY(fcntl)
+#endif
+#ifdef SYS_flock
+    ENTRY(flock)
+#endif
+#ifdef SYS_fork
+    ENTRY(fork)
+#endif
+#ifdef SYS_fstat
+    ENTRY(fstat)
+#endif
+#ifdef SYS_fstatfs
+    ENTRY(fstatfs)
+#endif
+#ifdef SYS_fsync
+    ENTRY(fsync)
+#endif
+#ifdef SYS_ftruncate
+    ENTRY(ftruncate)
+#endif
+#ifdef SYS_futex
+    ENTRY(futex)
+#endif
+#ifdef SYS_getcwd
+    ENTRY(getcwd)
+#endif
+#ifdef SYS_getdents
+    ENTRY(getdents)
+#endif
+#ifdef SYS_getdents64
+    ENTRY(getdents64)
+#endif
+#ifdef SYS_getegid
+    ENTRY(getegid)
+#endif
+#ifdef SYS_geteuid
+    ENTRY(geteuid)
+#endif
+#ifdef SYS_getgid
+    ENTRY(getgid)
+#endif
+#ifdef SYS_getgroups
+    ENTRY(getgroups)
+#endif
+#ifdef SYS_getpgrp
+    ENTRY(getpgrp)
+#endif
+#ifdef SYS_getpid
+    ENTRY(getpid)
+#endif
+#ifdef SYS_getppid
+    ENTRY(getppid)
+#endif
+#ifdef SYS_getrandom
+    ENTRY(getrandom)
+#endif
+#ifdef SYS_getrlimit
+    ENTRY(getrlimit)
+#endif
+#ifdef SYS_getrusage
+    ENTRY(getrusage)
+#endif
+#ifdef SYS_gettid
+    ENTRY(gettid)
+#endif
+#ifdef SYS_gettimeofday
+    ENTRY(gettimeofday)
+#endif
+#ifdef SYS_getuid
+    ENTRY(getuid)
+#endif
+#ifdef SYS_ioctl
+    ENTRY(ioctl)
+#endif
+#ifdef SYS_kill
+    ENTRY(kill)
+#endif
+#ifdef SYS_lseek
+    ENTRY(lseek)
+#endif
+#ifdef SYS_lstat
+    ENTRY(lstat)
+#endif
+#ifdef SYS_madvise
+    ENTRY(madvise)
+#endif
+#ifdef SYS_membarrier
+    ENTRY(membarrier)
+#endif
+#ifdef SYS_mkdir
+    ENTRY(mkdir)
+#endif
+#ifdef SYS_mkdirat
+    ENTRY(mkdirat)
+#endif
+#ifdef SYS_mmap
+    ENTRY(mmap)
+#endif
+#ifdef SYS_mprotect
+    ENTRY(mprotect)
+#endif
+#ifdef SYS_mremap
+    ENTRY(mremap)
+#endif
+#ifdef SYS_munmap
+    ENTRY(munmap)
+#endif
+#ifdef SYS_nanosleep
+    ENTRY(nanosleep)
+#endif
+#ifdef SYS_newfstatat
+    ENTRY(newfstatat)
+#endif
+#ifdef SYS_open
+    ENTRY(open)
+#endif
+#ifdef SYS_openat
+    ENTRY(openat)
+#endif
+#ifdef SYS_pipe
+    ENTRY(pipe)
+#endif
+#ifdef SYS_pipe2
+    ENTRY(pipe2)
+#endif
+#ifdef SYS_poll
+    ENTRY(poll)
+#endif
+#ifdef SYS_ppoll
+    ENTRY(ppoll)
+#endif
+#ifdef SYS_prctl
+    ENTRY(prctl)
+#endif
+#ifdef SYS_pread64
+    ENTRY(pread64)
+#endif
+#ifdef SYS_prlimit64
+    ENTRY(prlimit64)
+#endif
+#ifdef SYS_pselect6
+    ENTRY(pselect6)
+#endif
+#ifdef SYS_ptrace
+    ENTRY(ptrace)
+#endif
+#ifdef SYS_pwrite64
+    ENTRY(pwrite64)
+#endif
+#ifdef SYS_read
+    ENTRY(read)
+#endif
+#ifdef SYS_readlink
+    ENTRY(readlink)
+#endif
+#ifdef SYS_readlinkat
+    ENTRY(readlinkat)
+#endif
+#ifdef SYS_readv
+    ENTRY(readv)
+#endif
+#ifdef SYS_rename
+    ENTRY(rename)
+#endif
+#ifdef SYS_renameat
+    ENTRY(renameat)
+#endif
+#ifdef SYS_renameat2
+    ENTRY(renameat2)
+#endif
+#ifdef SYS_rmdir
+    ENTRY(rmdir)
+#endif
+#ifdef SYS_rseq
+    ENTRY(rseq)
+#endif
+#ifdef SYS_rt_sigaction
+    ENTRY(rt_sigaction)
+#endif
+#ifdef SYS_rt_sigprocmask
+    ENTRY(rt_sigprocmask)
+#endif
+#ifdef SYS_rt_sigreturn
+    ENTRY(rt_sigreturn)
+#endif
+#ifdef SYS_sched_getaffinity
+    ENTRY(sched_getaffinity)
+#endif
+#ifdef SYS_sched_yield
+    ENTRY(sched_yield)
+#endif
+#ifdef SYS_select
+    ENTRY(select)
+#endif
+#ifdef SYS_set_robust_list
+    ENTRY(set_robust_list)
+#endif
+#ifdef SYS_set_tid_address
+    ENTRY(set_tid_address)
+#endif
+#ifdef SYS_setpgid
+    ENTRY(setpgid)
+#endif
+#ifdef SYS_setrlimit
+    ENTRY(setrlimit)
+#endif
+#ifdef SYS_sigaltstack
+    ENTRY(sigaltstack)
+#endif
+#ifdef SYS_socket
+    ENTRY(socket)
+#endif
+#ifdef SYS_stat
+    ENTRY(stat)
+#endif
+#ifdef SYS_statfs
+    ENTRY(statfs)
+#endif
+#ifdef SYS_statx
+    ENTRY(statx)
+#endif
+#ifdef SYS_sysinfo
+    ENTRY(sysinfo)
+#endif
+#ifdef SYS_tgkill
+    ENTRY(tgkill)
+#endif
+#ifdef SYS_time
+    ENTRY(time)
+#endif
+#ifdef SYS_times
+    ENTRY(times)
+#endif
+#ifdef SYS_umask
+    ENTRY(umask)
+#endif
+#ifdef SYS_uname
+    ENTRY(uname)
+#endif
+#ifdef SYS_unlink
+    ENTRY(unlink)
+#endif
+#ifdef SYS_unlinkat
+    ENTRY(unlinkat)
+#endif
+#ifdef SYS_vfork
+    ENTRY(vfork)
+#endif
+#ifdef SYS_wait4
+    ENTRY(wait4)
+#endif
+#ifdef SYS_write
+    ENTRY(write)
+#endif
+#ifdef SYS_writev
+    ENTRY(writev)
+#endif
+#undef ENTRY
+};
+
+int syscall_nr_by_name(const char *name) {
+  size_t n = sizeof syscall_table / sizeof syscall_table[0];
+  for (size_t i = 0; i < n; i++) {
+    if (strcmp(syscall_table[i].name, name) == 0)
+      return syscall_table[i].nr;
+  }
+  return -1;
+}
+
+#ifdef SANDBOX_AUDIT_ARCH
+
+/* allow at most this number of syscalls in a filter */
+#define MAX_ALLOWED 512
+#define FILTER_LEN (MAX_ALLOWED * 2 + 32)
+
+int install_syscall_filter(const int *allowed, int len, const char *exec_path,
+                           int sock) {
+  if (len < 0 || len > MAX_ALLOWED) {
+    errno = EINVAL;
+    return -1;
+  }
+  struct sock_filter filter[FILTER_LEN];
+  unsigned short n = 0;
+
+#define PUSH(insn) (filter[n++] = (struct sock_filter)insn)
+
+  /* a filter for another architecture is meaningless */
+  PUSH(BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, arch)));
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, SANDBOX_AUDIT_ARCH, 1, 0));
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS));
+
+  PUSH(BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, nr)));
+#ifdef __x86_64__
+  /* reject x32 ABI */
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JGE | BPF_K, 0x40000000, 0, 1));
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS));
+#endif
+
+  for (int i = 0; i < len; i++) {
+    PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, (unsigned)allowed[i], 0, 1));
+    PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+  }
+
+  /* execve is only allowed for the path prepared by the sandbox */
+  unsigned long long path = (unsigned long long)(uintptr_t)exec_path;
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, SYS_execve, 0, 6));
+  PUSH(BPF_STMT(BPF_LD | BPF_W | BPF_ABS, ARG_LO(0)));
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, (unsigned)path, 0, 3));
+  PUSH(BPF_STMT(BPF_LD | BPF_W | BPF_ABS, ARG_HI(0)));
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, (unsigned)(path >> 32), 0, 1));
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_USER_NOTIF));
+
+  /* sendmsg is only allowed for passing the listener to the supervisor */
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, SYS_sendmsg, 0, 4));
+  PUSH(BPF_STMT(BPF_LD | BPF_W | BPF_ABS, ARG_LO(0)));
+  PUSH(BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, (unsigned)sock, 0, 1));
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_USER_NOTIF));
+
+  /* everything else is reported to the supervisor */
+  PUSH(BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_USER_NOTIF));
+#undef PUSH
+
+  struct sock_fprog prog = {.len = n, .filter = filter};
+
+  /* the socket is closed automatically by execve */
+  if (fcntl(sock, F_SETFD, FD_CLOEXEC) < 0)
+    return -1;
+  if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0)
+    return -1;
+  int listener = syscall(SYS_seccomp, SECCOMP_SET_MODE_FILTER,
+                         SECCOMP_FILTER_FLAG_NEW_LISTENER, &prog);
+  if (listener < 0)
+    return -1;
+
+  /* pass the listener to the supervisor */
+  char byte = 0;
+  struct iovec iov = {.iov_base = &byte, .iov_len = 1};
+  union {
+    char buf[CMSG_SPACE(sizeof(int))];
+    struct cmsghdr align;
+  } u;
+  memset(&u, 0, sizeof u);
+  struct msghdr msg = {0};
+  msg.msg_iov = &iov;
+  msg.msg_iovlen = 1;
+  msg.msg_control = u.buf;
+  msg.msg_controllen = sizeof u.buf;
+  struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+  cmsg->cmsg_level = SOL_SOCKET;
+  cmsg->cmsg_type = SCM_RIGHTS;
+  cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+  memcpy(CMSG_DATA(cmsg), &listener, sizeof listener);
+  if (sendmsg(sock, &msg, 0) < 0)
+    return -1;
+  return 0;
+}
+
+#else
+
+int install_syscall_filter(const int *allowed, int len, const char *exec_path,
+                           int sock) {
+  errno = ENOSYS;
+  return -1;
+}
+
+#endif
+
+int socketpair_unix(int *sv) { return socketpair(AF_UNIX, SOCK_STREAM, 0, sv); }
+
+int recv_fd(int sock) {
+  char byte;
+  struct iovec iov = {.iov_base = &byte, .iov_len = 1};
+  union {
+    char buf[CMSG_SPACE(sizeof(int))];
+    struct cmsghdr align;
+  } u;
+  struct msghdr msg = {0};
+  msg.msg_iov = &iov;
+  msg.msg_iovlen = 1;
+  msg.msg_control = u.buf;
+  msg.msg_controllen = sizeof u.buf;
+
+  ssize_t rc = recvmsg(sock, &msg, 0);
+  if (rc < 0)
+    return -1;
+  struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+  if (rc == 0 || cmsg == NULL || cmsg->cmsg_type != SCM_RIGHTS) {
+    /* peer closed before sending the listener */
+    errno = ECONNRESET;
+    return -1;
+  }
+  int fd;
+  memcpy(&fd, CMSG_DATA(cmsg), sizeof fd);
+  return fd;
+}
+
+int poll_notif(int listener, const sigset_t *mask, int timeout_ms) {
+  struct pollfd pfd = {.fd = listener, .events = POLLIN, .revents = 0};
+  struct timespec ts = {.tv_sec = timeout_ms / 1000,
+                        .tv_nsec = timeout_ms % 1000 * 1000000};
+  int rc = ppoll(&pfd, 1, timeout_ms < 0 ? NULL : &ts, mask);
+  if (rc < 0)
+    return errno == EINTR ? NOTIF_INTERRUPTED : -1;
+  if (pfd.revents & POLLIN)
+    return NOTIF_READY;
+  if (pfd.revents & (POLLHUP | POLLERR | POLLNVAL))
+    return NOTIF_CLOSED;
+  return NOTIF_INTERRUPTED;
+}
+
+int recv_notif_nr(int listener) {
+  struct seccomp_notif req;
+  memset(&req, 0, sizeof req);
+  if (ioctl(listener, SECCOMP_IOCTL_NOTIF_RECV, &req) < 0)
+    return -1;
+  return req.data.nr;
+}
+
+#else
+
+int syscall_nr_by_name(const char *name) { return -1; }
+
+int install_syscall_filter(const int *allowed, int len, const char *exec_path,
+                           int sock) {
+  errno = ENOSYS;
+  return -1;
+}
+
+int socketpair_unix(int *sv) {
+  errno = ENOSYS;
+  return -1;
+}
+
+int recv_fd(int sock) {
+  errno = ENOSYS;
+  return -1;
+}
+
+int poll_notif(int listener, const sigset_t *mask, int timeout_ms) {
+  errno = ENOSYS;
+  return -1;
+}
+
+int recv_notif_nr(int listener) {
+  errno = ENOSYS;
+  return -1;
+}
+
+#endif
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+/// 对程序可以使用的系统调用的限制，基于 seccomp-bpf 实现（仅支持 Linux x86_64 和 aarch64）
+///
+/// 调用了白名单以外的系统调用的程序会被立即终止，并得到 [`crate::Status::DangerousSyscall`]。
+/// 沙盒执行程序时使用的 `execve` 总是被允许的。
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum SyscallPolicy {
+    /// 不做任何限制
+    #[default]
+    #[serde(rename = "unrestricted")]
+    Unrestricted,
+    /// 只允许名单中的系统调用。当前架构上不存在的系统调用名称会被忽略
+    #[serde(rename = "allow_list")]
+    AllowList(Vec<String>),
+}
+
+/// 编译型语言产生的单线程程序（包括 glibc 的动态链接过程）所需的系统调用
+const NATIVE_SYSCALLS: &[&str] = &[
+    // io
+    "read",
+    "write",
+    "readv",
+    "writev",
+    "pread64",
+    "lseek",
+    "close",
+    "dup",
+    "dup2",
+    "dup3",
+    "fcntl",
+    "ioctl",
+    // file system (for dynamic linking and file io)
+    "open",
+    "openat",
+    "access",
+    "faccessat",
+    "faccessat2",
+    "stat",
+    "fstat",
+    "lstat",
+    "newfstatat",
+    "statx",
+    "readlink",
+    "readlinkat",
+    "getcwd",
+    // memory
+    "brk",
+    "mmap",
+    "munmap",
+    "mremap",
+    "mprotect",
+    "madvise",
+    // signal
+    "rt_sigaction",
+    "rt_sigprocmask",
+    "rt_sigreturn",
+    "sigaltstack",
+    "tgkill",
+    // process
+    "exit",
+    "exit_group",
+    "arch_prctl",
+    "set_tid_address",
+    "set_robust_list",
+    "rseq",
+    "futex",
+    "getpid",
+    "gettid",
+    "getuid",
+    "geteuid",
+    "getgid",
+    "getegid",
+    "getrlimit",
+    "prlimit64",
+    "getrusage",
+    "sched_yield",
+    "sched_getaffinity",
+    "uname",
+    "sysinfo",
+    // time
+    "clock_gettime",
+    "clock_getres",
+    "gettimeofday",
+    "time",
+    "times",
+    "nanosleep",
+    "clock_nanosleep",
+    // misc
+    "getrandom",
+    "poll",
+    "ppoll",
+];
+
+impl SyscallPolicy {
+    /// 运行 C/C++/Rust 等编译型语言生成的单线程程序所需的系统调用白名单
+    pub fn native() -> Self {
+        Self::AllowList(NATIVE_SYSCALLS.iter().map(|s| s.to_string()).collect())
+    }
+    /// 在白名单中添加系统调用，对 [`SyscallPolicy::Unrestricted`] 没有影响
+    pub fn allow<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        if let Self::AllowList(list) = &mut self {
+            for name in names {
+                if !list.iter().any(|s| s == name) {
+                    list.push(name.to_string());
+                }
+            }
+        }
+        self
+    }
+    /// 转换为当前架构下的系统调用号，`None` 表示不做限制
+    #[cfg(feature = "exec_sandbox")]
+    pub(crate) fn resolve(&self) -> Option<Vec<i32>> {
+        match self {
+            Self::Unrestricted => None,
+            Self::AllowList(list) => Some(
+                list.iter()
+                    .filter_map(|name| {
+                        let name = std::ffi::CString::new(name.as_str()).ok()?;
+                        super::sigsafe::syscall_nr(&name)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -14,6 +14,9 @@ pub use cbind::global_shared_t;
 #[derive(Clone)]
 pub struct GlobalShared(*mut global_shared_t);
 
+// the shared memory stays mapped until `free` is called
+unsafe impl Send for GlobalShared {}
+
 impl GlobalShared {
     pub fn init() -> Self {
         Self(unsafe { cbind::init_shared() })
@@ -37,6 +40,21 @@ impl GlobalShared {
             }
         }
     }
+    /// publish the pid of the tested process to the parent, see [`GlobalShared::tested_pid`]
+    pub fn set_tested_pid(&self, pid: i32) {
+        if !self.0.is_null() {
+            unsafe { std::ptr::addr_of_mut!((*self.0).tested_pid).write_volatile(pid) }
+        }
+    }
+    /// pid of the running tested process, which is written by another process
+    #[cfg(feature = "async")]
+    pub fn tested_pid(&self) -> Option<i32> {
+        if self.0.is_null() {
+            return None;
+        }
+        let pid = unsafe { std::ptr::addr_of!((*self.0).tested_pid).read_volatile() };
+        (pid > 0).then_some(pid)
+    }
     pub fn free(&self) {
         unsafe {
             cbind::free_shared(self.0);
@@ -56,6 +74,10 @@ pub fn _get_rusage_children() -> Result<cbind::rusage_t, Errno> {
                 tv_usec: 0,
             },
             ru_maxrss: 0,
+            ru_minflt: 0,
+            ru_majflt: 0,
+            ru_nvcsw: 0,
+            ru_nivcsw: 0,
         };
         let rc = cbind::get_children_rusage(&mut rusage as *mut cbind::rusage_t);
         if rc < 0 {
@@ -77,6 +99,10 @@ pub fn get_rusage_self() -> Result<cbind::rusage_t, Errno> {
                 tv_usec: 0,
             },
             ru_maxrss: 0,
+            ru_minflt: 0,
+            ru_majflt: 0,
+            ru_nvcsw: 0,
+            ru_nivcsw: 0,
         };
         let rc = cbind::get_self_rusage(&mut rusage as *mut cbind::rusage_t);
         if rc < 0 {
@@ -100,6 +126,10 @@ pub fn wait_rusage(pid: i32, options: u32) -> Result<(i32, WaitStatus, cbind::ru
                 tv_usec: 0,
             },
             ru_maxrss: 0,
+            ru_minflt: 0,
+            ru_majflt: 0,
+            ru_nvcsw: 0,
+            ru_nivcsw: 0,
         };
         let mut status = 0;
         let rc = cbind::wait_rusage(
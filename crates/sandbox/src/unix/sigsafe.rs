@@ -202,6 +202,28 @@ impl SigblockGuard {
     pub fn suspend(&self) {
         sigsuspend(&self.0)
     }
+    /// Similar to [`SigblockGuard::suspend`], but also returns when the
+    /// seccomp notification listener (if any) is ready, or after `timeout_ms`
+    /// milliseconds (if any), which is reported as [`NotifPoll::Interrupted`].
+    pub fn poll_notif(
+        &self,
+        listener: Option<i32>,
+        timeout_ms: Option<i32>,
+    ) -> Result<NotifPoll, Errno> {
+        let rc = unsafe {
+            cbind::poll_notif(
+                listener.unwrap_or(-1),
+                &self.0 as *const Sigset,
+                timeout_ms.unwrap_or(-1),
+            )
+        };
+        match rc as u32 {
+            cbind::NOTIF_READY => Ok(NotifPoll::Ready),
+            cbind::NOTIF_CLOSED => Ok(NotifPoll::Closed),
+            cbind::NOTIF_INTERRUPTED => Ok(NotifPoll::Interrupted),
+            _ => errno_result(),
+        }
+    }
 }
 
 impl Drop for SigblockGuard {
@@ -232,9 +254,11 @@ pub const RLIMIT_STACK: u32 = cbind::__rlimit_resource_RLIMIT_STACK;
 pub const RLIMIT_FSIZE: u32 = cbind::__rlimit_resource_RLIMIT_FSIZE;
 #[cfg(target_os = "linux")]
 pub const RLIMIT_NOFILE: u32 = cbind::__rlimit_resource_RLIMIT_NOFILE;
+#[cfg(target_os = "linux")]
+pub const RLIMIT_NPROC: u32 = cbind::__rlimit_resource_RLIMIT_NPROC;
 
 #[cfg(target_os = "macos")]
-pub use {cbind::RLIMIT_FSIZE, cbind::RLIMIT_NOFILE};
+pub use {cbind::RLIMIT_FSIZE, cbind::RLIMIT_NOFILE, cbind::RLIMIT_NPROC};
 
 pub fn setrlimit(resource: i32, rlim_cur: u64, rlim_max: u64) -> Result<(), Errno> {
     unsafe {
@@ -272,7 +296,7 @@ impl WaitStatus {
     }
 }
 
-pub use cbind::{ECHILD, WNOHANG};
+pub use cbind::{EBUSY, ECHILD, WNOHANG};
 /// return (pid, status)
 pub fn waitpid(pid: i32, options: u32) -> Result<(i32, WaitStatus), Errno> {
     unsafe {
@@ -286,6 +310,32 @@ pub fn waitpid(pid: i32, options: u32) -> Result<(i32, WaitStatus), Errno> {
     }
 }
 
+/// wait for the child `pid` to exit, but leave it waitable (thus its pid is not reused)
+#[cfg(feature = "async")]
+pub fn wait_exited_nowait(pid: i32) -> Result<(), Errno> {
+    let r = unsafe { cbind::wait_exited_nowait(pid) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// After this, receiving SIGTERM sets the flag of [`cancel_requested`] instead of terminating.
+pub fn register_cancel_handler() -> Result<(), Errno> {
+    let r = unsafe { cbind::register_cancel_handler() };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// whether SIGTERM has been received since [`register_cancel_handler`]
+pub fn cancel_requested() -> bool {
+    unsafe { cbind::cancel_requested() != 0 }
+}
+
 pub fn sigsuspend(sigmask: &Sigset) {
     unsafe {
         let rc = cbind::sigsuspend(sigmask as *const Sigset);
@@ -307,9 +357,10 @@ pub fn signal_echo(signo: u32) {
     }
 }
 
-pub fn sleep(sec: u32) {
+/// sleep for `ms` milliseconds, not interrupted by signals
+pub fn sleep_ms(ms: u64) {
     unsafe {
-        cbind::sleep(sec);
+        cbind::sleep_ms(ms);
     }
 }
 
@@ -335,6 +386,285 @@ pub fn get_sigkill() -> u32 {
 pub fn get_sigxcpu() -> u32 {
     unsafe { cbind::get_sigxcpu() as u32 }
 }
+pub fn get_sigxfsz() -> u32 {
+    unsafe { cbind::get_sigxfsz() as u32 }
+}
+#[cfg(feature = "async")]
+pub fn get_sigterm() -> u32 {
+    unsafe { cbind::get_sigterm() as u32 }
+}
+
+/// name of a signal (e.g. "SIGSEGV"), or None if unknown
+pub fn signal_name(signo: i32) -> Option<&'static str> {
+    let name = unsafe { cbind::signal_abbrev(signo) };
+    if name.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(name) }.to_str().ok()
+    }
+}
+
+/// Resolve the syscall number of current architecture by its name.
+///
+/// This function is not required to be async-signal-safe.
+pub fn syscall_nr(name: &CStr) -> Option<i32> {
+    let nr = unsafe { cbind::syscall_nr_by_name(name.as_ptr()) };
+    (nr >= 0).then_some(nr)
+}
+
+/// write the whole buffer to the file descriptor
+pub fn write(fd: i32, buf: &[u8]) -> Result<(), Errno> {
+    let mut buf = buf;
+    while !buf.is_empty() {
+        let count = unsafe { cbind::write(fd, buf.as_ptr().cast(), buf.len()) };
+        if count < 0 {
+            return errno_result();
+        }
+        buf = &buf[count as usize..];
+    }
+    Ok(())
+}
+
+/// create a pipe, return (read end, write end). Both ends are closed on exec.
+pub fn pipe() -> Result<(i32, i32), Errno> {
+    let mut fds = [0; 2];
+    let r = unsafe { cbind::pipe_cloexec(fds.as_mut_ptr()) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok((fds[0], fds[1]))
+    }
+}
+
+/// create an anonymous in-memory file (linux only), which is closed on exec.
+pub fn anon_file() -> Result<i32, Errno> {
+    let r = unsafe { cbind::anon_file() };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(r)
+    }
+}
+
+/// monotonic time in nanoseconds
+pub fn monotonic_ns() -> i64 {
+    unsafe { cbind::monotonic_ns() }
+}
+
+/// CPU time in nanoseconds consumed by the child process `pid` (linux only)
+pub fn process_cputime_ns(pid: i32) -> Result<i64, Errno> {
+    let r = unsafe { cbind::process_cputime_ns(pid) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(r)
+    }
+}
+
+/// the number of threads whose real uid is `uid`, i. e. what `RLIMIT_NPROC` counts (linux only)
+pub fn count_user_threads(uid: u32) -> Result<u64, Errno> {
+    let r = unsafe { cbind::count_user_threads(uid) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(r as u64)
+    }
+}
+
+/// pin the calling process to the given CPUs (linux only)
+pub fn set_cpu_affinity(cpus: &[u32]) -> Result<(), Errno> {
+    let r = unsafe { cbind::set_cpu_affinity(cpus.as_ptr(), cpus.len() as i32) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// disable address space layout randomization, which is kept after execve (linux only)
+pub fn disable_aslr() -> Result<(), Errno> {
+    let r = unsafe { cbind::disable_aslr() };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// set the nice value of the calling process
+pub fn set_nice(nice: i32) -> Result<(), Errno> {
+    let r = unsafe { cbind::set_nice(nice) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// open a counter of the retired user-space instructions of the children forked afterwards,
+/// see `open_instruction_counter` in utils.h
+pub fn open_instruction_counter() -> Result<i32, Errno> {
+    let r = unsafe { cbind::open_instruction_counter() };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(r)
+    }
+}
+
+/// read the value of a perf counter
+pub fn read_counter(fd: i32) -> Option<u64> {
+    let r = unsafe { cbind::read_counter(fd) };
+    (r >= 0).then_some(r as u64)
+}
+
+/// replace the supplementary groups with `groups`, then switch to `gid` and `uid` if given
+pub fn drop_privileges(uid: Option<u32>, gid: Option<u32>, groups: &[u32]) -> Result<(), Errno> {
+    let r = unsafe {
+        cbind::drop_privileges(
+            uid.map_or(-1, |u| u as i32),
+            gid.map_or(-1, |g| g as i32),
+            groups.as_ptr(),
+            groups.len() as i32,
+        )
+    };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// create a pair of connected unix sockets
+pub fn socketpair() -> Result<(i32, i32), Errno> {
+    let mut sv = [0; 2];
+    unsafe {
+        if cbind::socketpair_unix(sv.as_mut_ptr()) < 0 {
+            errno_result()
+        } else {
+            Ok((sv[0], sv[1]))
+        }
+    }
+}
+
+pub fn close(fd: i32) {
+    unsafe {
+        cbind::close(fd);
+    }
+}
+
+/// Install a seccomp filter which only allows syscalls in `allowed`, then send
+/// the notification listener through `sock`. Other syscalls will be blocked and
+/// reported to the listener.
+///
+/// `execve` is only allowed with `path` as its first argument.
+pub fn install_syscall_filter(allowed: &[i32], path: &CStr, sock: i32) -> Result<(), Errno> {
+    unsafe {
+        let rc = cbind::install_syscall_filter(
+            allowed.as_ptr(),
+            allowed.len() as i32,
+            path.as_ptr(),
+            sock,
+        );
+        if rc < 0 {
+            errno_result()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// receive a file descriptor sent by [`install_syscall_filter`]
+pub fn recv_fd(sock: i32) -> Result<i32, Errno> {
+    unsafe {
+        let fd = cbind::recv_fd(sock);
+        if fd < 0 {
+            errno_result()
+        } else {
+            Ok(fd)
+        }
+    }
+}
+
+/// state of the seccomp notification listener
+pub enum NotifPoll {
+    /// a forbidden syscall is waiting for response
+    Ready,
+    /// interrupted by a signal
+    Interrupted,
+    /// all filtered processes have exited
+    Closed,
+}
+
+/// return the number of the syscall reported by the listener
+pub fn recv_notif_nr(listener: i32) -> Result<i32, Errno> {
+    unsafe {
+        let nr = cbind::recv_notif_nr(listener);
+        if nr < 0 {
+            errno_result()
+        } else {
+            Ok(nr)
+        }
+    }
+}
+
+/// a path to be bind mounted into the isolated root
+pub type BindMount = cbind::bind_mount_t;
+
+/// enter new user (if possible) and pid namespaces.
+///
+/// The new pid namespace only applies to the children forked afterwards.
+/// If `switch_user` and the caller is root, the user namespace is skipped,
+/// since the user to switch to is not mapped in it.
+pub fn enter_user_pid_ns(switch_user: bool) -> Result<(), Errno> {
+    let r = unsafe { cbind::enter_user_pid_ns(switch_user as i32) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// enter new mount, network, ipc and uts namespaces, then pivot into
+/// a tmpfs mounted on `root` with `binds` mounted.
+///
+/// The capabilities are dropped such that the program executed later can not
+/// remount the binds, while the current process is still able to switch user.
+pub fn setup_isolated_root(
+    root: &CStr,
+    binds: &[BindMount],
+    workdir: Option<&CStr>,
+) -> Result<(), Errno> {
+    let r = unsafe {
+        cbind::setup_isolated_root(
+            root.as_ptr(),
+            binds.as_ptr(),
+            binds.len() as i32,
+            workdir.map(|s| s.as_ptr()).unwrap_or(std::ptr::null()),
+        )
+    };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// enter a new mount namespace, mount a tmpfs with `options` on `dir` and change
+/// the working directory to it.
+pub fn mount_scratch(dir: &CStr, options: &CStr) -> Result<(), Errno> {
+    let r = unsafe { cbind::mount_scratch(dir.as_ptr(), options.as_ptr()) };
+    if r < 0 {
+        errno_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// whether the space or inodes of the tmpfs mounted on `dir` is used up
+pub fn scratch_full(dir: &CStr) -> bool {
+    unsafe { cbind::scratch_full(dir.as_ptr()) > 0 }
+}
 
 #[cfg(test)]
 mod tests {
@@ -12,17 +12,25 @@ macro_rules! print {
 }
 
 use crate::{
-    unix::{share_mem, sigsafe, Limitation},
+    unix::{
+        cgroup::Cgroup,
+        isolation::{PreparedRoot, PreparedScratch},
+        share_mem, sigsafe, Limitation,
+    },
     Elapse, Memory, Status, Termination,
 };
 use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 use std::{
     ffi::{CStr, CString},
-    io::Write,
-    time::Instant,
+    fs::File,
+    io::{Read, Seek, Write},
+    os::fd::{AsRawFd, FromRawFd},
 };
 
+/// 检查被测程序 CPU 时间的间隔（毫秒）
+const CPU_POLL_INTERVAL: i32 = 10;
+
 /// 执行单个可执行文件
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(missing_docs)]
@@ -37,6 +45,168 @@ pub struct Singleton {
     // otherwise they are set to `/dev/null`.
     pub stdout: CString,
     pub stderr: CString,
+    /// 允许使用的系统调用号，为 None 表示不做限制
+    pub syscalls: Option<Vec<i32>>,
+    /// 在该 cgroup v2 目录下统计和限制资源，为 None 表示使用 rlimit，
+    /// 详见 [`super::ResourceBackend::Cgroup`]
+    pub cgroup: Option<String>,
+    /// 为 None 表示不使用 namespace 隔离
+    pub isolation: Option<super::Isolation>,
+    /// 将被测程序绑定到这些 CPU 上，为空表示不绑定（仅支持 Linux）
+    pub cpus: Vec<u32>,
+    /// 关闭地址空间随机化（仅支持 Linux）
+    pub no_aslr: bool,
+    /// 被测程序的 nice 值，为 None 表示不修改
+    pub nice: Option<i32>,
+    /// 被测程序的 CPU 时间在这段时间内没有增长时将其终止（仅支持 Linux），为 None 表示不检测
+    pub idle_limit: Option<Elapse>,
+    /// 从内存中提供标准输入，优先于 `stdin`
+    pub stdin_data: Option<Vec<u8>>,
+    /// 将标准输出捕获到内存中（至多这么多字节），优先于 `stdout`
+    pub capture_stdout: Option<Memory>,
+    /// 将标准错误输出捕获到内存中（至多这么多字节），优先于 `stderr`
+    pub capture_stderr: Option<Memory>,
+    /// 以该用户执行被测程序，为 None 表示不切换（需要 root 权限）
+    pub uid: Option<u32>,
+    /// 以该用户组执行被测程序，为 None 表示不切换（需要 root 权限）
+    pub gid: Option<u32>,
+    /// 切换用户时被测程序的附加用户组
+    pub groups: Vec<u32>,
+    /// 统计被测程序执行的指令数（仅支持 Linux），设置了指令数限制时总是统计
+    pub count_instructions: bool,
+    /// 以该大小的 tmpfs 作为工作目录（需要 root 权限或者使用 namespace 隔离），为 None 表示不使用
+    pub scratch: Option<Memory>,
+}
+
+/// pipes connected to the standard IO, used by [`super::Pipeline`]
+///
+/// in-memory standard IO is also passed this way, see [`MemoryStdio`]
+#[derive(Default, Clone)]
+pub(crate) struct PipeStdio {
+    pub stdin: Option<i32>,
+    pub stdout: Option<i32>,
+    pub stderr: Option<i32>,
+    /// all pipe ends created by the pipeline, which should be closed in the child
+    pub all: Vec<i32>,
+}
+
+impl PipeStdio {
+    fn own(&self) -> impl Iterator<Item = i32> {
+        self.stdin.into_iter().chain(self.stdout).chain(self.stderr)
+    }
+    fn close_others(&self) {
+        for &fd in &self.all {
+            if !self.own().any(|own| own == fd) {
+                sigsafe::close(fd);
+            }
+        }
+    }
+    fn close_own(&self) {
+        self.own().for_each(sigsafe::close);
+    }
+}
+
+/// in-memory standard IO of a program, backed by anonymous files (memfd on linux).
+///
+/// regular files are used instead of pipes, since the supervisors of other programs
+/// forked concurrently may hold the pipe ends and delay the EOF.
+#[derive(Default)]
+struct MemoryStdio {
+    stdin: Option<File>,
+    stdout: Option<File>,
+    stderr: Option<File>,
+}
+
+impl MemoryStdio {
+    /// create the in-memory files which are not connected by the pipeline
+    fn create(singleton: &Singleton, stdio: &PipeStdio) -> std::io::Result<(Self, PipeStdio)> {
+        let mut mem = Self::default();
+        let mut stdio = stdio.clone();
+        if let (None, Some(data)) = (stdio.stdin, &singleton.stdin_data) {
+            let mut file = anon_file()?;
+            file.write_all(data)?;
+            file.rewind()?;
+            stdio.stdin = Some(file.as_raw_fd());
+            mem.stdin = Some(file);
+        }
+        if let (None, Some(_)) = (stdio.stdout, singleton.capture_stdout) {
+            let file = anon_file()?;
+            stdio.stdout = Some(file.as_raw_fd());
+            mem.stdout = Some(file);
+        }
+        if let (None, Some(_)) = (stdio.stderr, singleton.capture_stderr) {
+            let file = anon_file()?;
+            stdio.stderr = Some(file.as_raw_fd());
+            mem.stderr = Some(file);
+        }
+        Ok((mem, stdio))
+    }
+}
+
+fn anon_file() -> std::io::Result<File> {
+    match sigsafe::anon_file() {
+        // SAFETY: the file descriptor is newly created and owned by nobody else
+        Ok(fd) => Ok(unsafe { File::from_raw_fd(fd) }),
+        Err(_) => tempfile::tempfile(),
+    }
+}
+
+/// read at most `cap` bytes of the captured output, return whether it exceeds `cap`
+fn read_captured(
+    file: Option<File>,
+    cap: Option<Memory>,
+) -> std::io::Result<(Option<Vec<u8>>, bool)> {
+    let (Some(mut file), Some(cap)) = (file, cap) else {
+        return Ok((None, false));
+    };
+    file.rewind()?;
+    let mut buf = Vec::new();
+    file.take(cap.byte() + 1).read_to_end(&mut buf)?;
+    let exceeded = buf.len() as u64 > cap.byte();
+    buf.truncate(cap.byte() as usize);
+    Ok((Some(buf), exceeded))
+}
+
+/// resources prepared by the parent before forking
+struct Prepared<'a> {
+    cgroup: Option<&'a Cgroup>,
+    root: Option<&'a PreparedRoot>,
+    scratch: Option<&'a PreparedScratch>,
+    stdio: &'a PipeStdio,
+}
+
+/// 已经启动的程序，需要调用 [`Singleton::wait`] 等待其结束
+pub(crate) struct Running {
+    pid: i32,
+    /// see [`sigsafe::monotonic_ns`]
+    start: i64,
+    shared: share_mem::GlobalShared,
+    cgroup: Option<Cgroup>,
+    // the mount point of the new root, removed after the program exits
+    _root: Option<PreparedRoot>,
+    // the mount point of the scratch space, removed after the program exits
+    _scratch: Option<PreparedScratch>,
+    captured: MemoryStdio,
+}
+
+#[cfg(feature = "async")]
+impl Running {
+    /// ask the supervisor to kill the tested process
+    pub(crate) fn cancel(&self) -> Result<(), sigsafe::Errno> {
+        sigsafe::kill(self.pid, sigsafe::get_sigterm())
+    }
+    /// pid of the supervisor (the direct child)
+    pub(crate) fn pid(&self) -> i32 {
+        self.pid
+    }
+    /// pid of the tested process, or None if it has not started or has been reaped
+    pub(crate) fn tested_pid(&self) -> Option<i32> {
+        self.shared.tested_pid()
+    }
+    /// see [`sigsafe::monotonic_ns`]
+    pub(crate) fn start(&self) -> i64 {
+        self.start
+    }
 }
 
 impl Singleton {
@@ -49,9 +219,21 @@ impl Singleton {
         env: &[*mut std::ffi::c_char],
         guard: sigsafe::SigblockGuard,
         shared: share_mem::GlobalShared,
+        prepared: Prepared,
     ) -> Result<(), sigsafe::Errno> {
+        let Prepared {
+            cgroup,
+            root,
+            scratch,
+            stdio,
+        } = prepared;
+        // pipe ends of other processes must not be held, otherwise they never get EOF
+        stdio.close_others();
+
         // register a handler for SIGCHLD to make sigsuspend work
         sigsafe::signal_echo(sigsafe::get_sigchld());
+        // SIGTERM from the parent asks to kill the tested process
+        sigsafe::register_cancel_handler()?;
 
         let max_rss_before = share_mem::get_rusage_self()
             .map(|o| o.ru_maxrss)
@@ -59,6 +241,53 @@ impl Singleton {
 
         seprintln!("(child) self's max_rss before execve: {max_rss_before}");
 
+        seprintln!("(child) fork a timmer");
+
+        // at this time, all signals are blocked, so we can fork directly.
+        // the timer is forked before entering the new pid namespace, since
+        // no process can be forked into it after its init process exits
+        let pid_timer = match self.limits.real_time {
+            super::Lim::Single(s) | super::Lim::Double(s, _) => {
+                // fork a process to setup timer
+                let pid = sigsafe::fork()?;
+                if pid == 0 {
+                    stdio.close_own();
+                    seprintln!("(child-timer) sleep for {} ms", s.ms());
+                    sigsafe::sleep_ms(s.ms());
+                    seprintln!("(child-timer) timmer exit");
+                    sigsafe::exit(0);
+                }
+                Some(pid)
+            }
+        };
+
+        // opened before entering the user namespace, in which the counter may not be permitted
+        let counter = if self.count_instructions || self.limits.instructions.is_some() {
+            sigsafe::open_instruction_counter()
+                .map_err(|e| seprintln!("(child) instruction counter unavailable: {e}"))
+                .ok()
+        } else {
+            None
+        };
+
+        // the dedicated user whose processes are limited by RLIMIT_NPROC
+        let nproc_uid = self.uid.filter(|_| cgroup.is_none());
+
+        if root.is_some() {
+            // the tested process will be the init process of the new pid namespace
+            sigsafe::enter_user_pid_ns(self.switches_user())?;
+        }
+        // mounted by the supervisor, so that its usage can be checked after the tested process exits
+        if let Some(scratch) = scratch {
+            scratch.mount()?;
+        }
+
+        // the tested process sends its seccomp notification listener through this socket
+        let sock = match self.syscalls {
+            Some(_) => Some(sigsafe::socketpair()?),
+            None => None,
+        };
+
         // fork another child process to execute program
         // at this time, all signals are blocked, so we can fork directly
         let pid_child = sigsafe::fork()?;
@@ -66,6 +295,11 @@ impl Singleton {
             seprintln!("(child-child) pid = {}", sigsafe::getpid());
             sigsafe::set_self_grp();
 
+            // join the cgroup before allocating any resource
+            if let Some(cg) = cgroup {
+                sigsafe::write(cg.procs_fd(), b"0")?;
+            }
+
             // set resource limit
             macro_rules! setlim {
                 ($i:ident, $r:ident, $f:ident) => {
@@ -81,53 +315,131 @@ impl Singleton {
             }
             // https://issues.chromium.org/issues/40581251#comment3
             // https://issues.fast-downward.org/issue825
+            // RLIMIT_CPU only works in seconds, the precise limit is checked by the supervisor
             #[cfg(not(target_os = "macos"))]
-            setlim!(cpu_time, RLIMIT_CPU, sec);
+            setlim!(cpu_time, RLIMIT_CPU, sec_ceil);
             #[cfg(not(target_os = "macos"))]
             setlim!(virtual_memory, RLIMIT_AS, byte);
             #[cfg(not(target_os = "macos"))]
             setlim!(stack_memory, RLIMIT_STACK, byte);
-            setlim!(output_memory, RLIMIT_FSIZE, byte);
+            // one more byte is allowed, so that an output of exactly the limit is not
+            // distinguished from a truncated one (see `output_exceeded`)
+            let (soft, hard) = match self.limits.output_memory {
+                super::Lim::Single(s) => (s, s),
+                super::Lim::Double(s, h) => (s, h),
+            };
+            sigsafe::setrlimit(
+                sigsafe::RLIMIT_FSIZE as i32,
+                soft.byte().saturating_add(1),
+                hard.byte().saturating_add(1),
+            )?;
             setlim!(fileno, RLIMIT_NOFILE, into);
+            // pids.max is used instead if the cgroup is available. RLIMIT_NPROC counts all
+            // processes of the user (and is ignored for root), thus it is only set when
+            // switching to a dedicated user. One more process is allowed, so that the
+            // supervisor can observe the excess, since failed forks are not reported
+            if nproc_uid.is_some() {
+                let (soft, hard) = match self.limits.nproc {
+                    super::Lim::Single(s) => (s, s),
+                    super::Lim::Double(s, h) => (s, h),
+                };
+                sigsafe::setrlimit(
+                    sigsafe::RLIMIT_NPROC as i32,
+                    soft.saturating_add(1),
+                    hard.saturating_add(1),
+                )?;
+            }
             seprintln!("(child-child) resource limited");
 
+            // reduce the fluctuation of running time
+            if !self.cpus.is_empty() {
+                sigsafe::set_cpu_affinity(&self.cpus)?;
+            }
+            if self.no_aslr {
+                sigsafe::disable_aslr()?;
+            }
+            if let Some(nice) = self.nice {
+                sigsafe::set_nice(nice)?;
+            }
+
             // redirect standard IO
-            if let Some(stdin) = &self.stdin {
+            if let Some(fd) = stdio.stdin {
+                sigsafe::dup2(fd, sigsafe::STDIN_FILENO);
+            } else if let Some(stdin) = &self.stdin {
                 let fd = sigsafe::open_read(stdin)?;
                 sigsafe::dup2(fd, sigsafe::STDIN_FILENO);
             }
-            let fd = sigsafe::open_write(&self.stdout)?;
-            sigsafe::dup2(fd, sigsafe::STDOUT_FILENO);
-            let fd = sigsafe::open_write(&self.stderr)?;
-            sigsafe::dup2(fd, sigsafe::STDERR_FILENO);
+            if let Some(fd) = stdio.stdout {
+                sigsafe::dup2(fd, sigsafe::STDOUT_FILENO);
+            } else {
+                let fd = sigsafe::open_write(&self.stdout)?;
+                sigsafe::dup2(fd, sigsafe::STDOUT_FILENO);
+            }
+            if let Some(fd) = stdio.stderr {
+                sigsafe::dup2(fd, sigsafe::STDERR_FILENO);
+            } else {
+                let fd = sigsafe::open_write(&self.stderr)?;
+                sigsafe::dup2(fd, sigsafe::STDERR_FILENO);
+            }
+
+            // switch to the isolated root after the standard IO files are opened
+            if let Some(root) = root {
+                root.enter()?;
+            }
+
+            // drop privileges after everything requiring them is done
+            if self.switches_user() {
+                sigsafe::drop_privileges(self.uid, self.gid, &self.groups)?;
+            }
 
             drop(guard); // unblock signals
-                         // todo: set syscall limit
+
+            // set syscall limit, which must be the last step before execve
+            if let (Some(allowed), Some((sock_sup, sock))) = (&self.syscalls, sock) {
+                sigsafe::close(sock_sup);
+                sigsafe::install_syscall_filter(allowed, path, sock)?;
+            }
             sigsafe::execve(path, args, env);
         }
+        stdio.close_own();
+        shared.set_tested_pid(pid_child);
 
-        seprintln!("(child) fork a timmer");
-
-        // at this time, all signals are blocked, so we can fork directly
-        let pid_timer = match self.limits.real_time {
-            super::Lim::Single(s) | super::Lim::Double(s, _) => {
-                // fork a process to setup timer
-                let pid = sigsafe::fork()?;
-                if pid == 0 {
-                    let secs = s.sec().clamp(0, u32::MAX as u64) as u32;
-                    seprintln!("(child-timer) sleep for {secs} seconds");
-                    sigsafe::sleep(secs);
-                    seprintln!("(child-timer) timmer exit");
-                    sigsafe::exit(0);
+        let mut listener = match sock {
+            Some((sock_sup, sock)) => {
+                sigsafe::close(sock);
+                let r = sigsafe::recv_fd(sock_sup);
+                sigsafe::close(sock_sup);
+                match r {
+                    Ok(fd) => Some(fd),
+                    Err(e) => {
+                        // the tested process failed before installing the filter
+                        seprintln!("(child) receive seccomp listener failed: {e}");
+                        None
+                    }
                 }
-                Some(pid)
             }
+            None => None,
         };
 
         // can be interrupted either by timer or child process
         let mut timer_first = false;
         let mut child_status = None;
         let mut child_rusage = None;
+        let mut dangerous_syscall = -1;
+        let mut idle_killed = false;
+        let mut nproc_killed = false;
+        let mut cancelled = false;
+        let mut exit_time = 0;
+
+        // RLIMIT_CPU is not precise enough, thus the CPU time of the tested
+        // process is checked periodically (linux only)
+        let poll_cpu = cfg!(target_os = "linux");
+        let (super::Lim::Single(cpu_max) | super::Lim::Double(_, cpu_max)) = self.limits.cpu_time;
+        // the CPU time of the tested process and when it was last seen growing
+        let mut last_cputime = -1;
+        let mut last_active = sigsafe::monotonic_ns();
+        // same as pids.max, the hard limit is checked
+        let (super::Lim::Single(nproc_max) | super::Lim::Double(_, nproc_max)) = self.limits.nproc;
 
         seprintln!("(child) wait for tested process and timer");
 
@@ -135,8 +447,84 @@ impl Singleton {
             // notice that sigsuspend only interrupts for signals whose action is
             // either calling handler function or exit (thus sometimes you need to
             // register handler for a signal to make it work).
-            guard.suspend();
+            if listener.is_some() || poll_cpu {
+                match guard.poll_notif(listener, poll_cpu.then_some(CPU_POLL_INTERVAL)) {
+                    Ok(sigsafe::NotifPoll::Ready) => {
+                        if let Some(Ok(nr)) = listener.map(sigsafe::recv_notif_nr) {
+                            seprintln!("(child) tested process calls forbidden syscall {nr}");
+                            dangerous_syscall = nr;
+                            // the tested process is blocked in the syscall, thus it hasn't been reaped
+                            if let Err(e) = sigsafe::kill(pid_child, sigsafe::get_sigkill()) {
+                                seprintln!("(child) kill child failed");
+                                break 'outer Err(e);
+                            }
+                        }
+                    }
+                    Ok(sigsafe::NotifPoll::Closed) => {
+                        if let Some(fd) = listener.take() {
+                            sigsafe::close(fd);
+                        }
+                    }
+                    Ok(sigsafe::NotifPoll::Interrupted) => {}
+                    Err(e) => {
+                        seprintln!("(child) poll seccomp listener failed");
+                        break 'outer Err(e);
+                    }
+                }
+            } else {
+                guard.suspend();
+            }
             seprintln!("(child) suspend over");
+            if !cancelled && child_status.is_none() && sigsafe::cancel_requested() {
+                seprintln!("(child) cancelled by parent");
+                cancelled = true;
+                // the tested process hasn't been reaped, thus it's pid is not freed
+                if let Err(e) = sigsafe::kill(pid_child, sigsafe::get_sigkill()) {
+                    seprintln!("(child) kill child failed");
+                    break 'outer Err(e);
+                }
+            }
+            if poll_cpu && child_status.is_none() {
+                // the tested process hasn't been reaped, thus it's pid is not freed
+                let now = sigsafe::monotonic_ns();
+                // a failed read is neither counted as running nor idle, the poll is skipped
+                let (exceeded, idle) = match sigsafe::process_cputime_ns(pid_child) {
+                    Ok(cputime) => {
+                        if cputime > last_cputime {
+                            last_cputime = cputime;
+                            last_active = now;
+                        }
+                        let idle = self.idle_limit.is_some_and(|idle| {
+                            (now - last_active) as u64 / 1_000_000 >= idle.ms()
+                        });
+                        (cputime as u64 / 1_000_000 > cpu_max.ms(), idle)
+                    }
+                    Err(e) => {
+                        seprintln!("(child) read cpu time failed: {e}");
+                        (false, false)
+                    }
+                };
+                let nproc_exceeded = nproc_uid.is_some_and(|uid| {
+                    sigsafe::count_user_threads(uid).is_ok_and(|n| n > nproc_max)
+                });
+                if exceeded || idle || nproc_exceeded {
+                    if exceeded {
+                        seprintln!("(child) tested process exceeds cpu time limit");
+                    } else if nproc_exceeded {
+                        seprintln!("(child) tested process exceeds process limit");
+                        nproc_killed = true;
+                    } else {
+                        seprintln!("(child) tested process is idle for too long");
+                        idle_killed = true;
+                    }
+                    // the whole process group is killed to release the processes of the user
+                    let target = if nproc_killed { -pid_child } else { pid_child };
+                    if let Err(e) = sigsafe::kill(target, sigsafe::get_sigkill()) {
+                        seprintln!("(child) kill child failed");
+                        break 'outer Err(e);
+                    }
+                }
+            }
             loop {
                 // since all signals are blocked, SIGCHLD will not interrupt
                 let r = share_mem::wait_rusage(-1, sigsafe::WNOHANG);
@@ -154,7 +542,9 @@ impl Singleton {
                                 }
                             } // otherwise timer is killed, ignored
                         } else if pid_child == pid {
+                            exit_time = sigsafe::monotonic_ns();
                             child_status = Some(status);
+                            shared.set_tested_pid(0);
                             child_rusage = Some(ru);
                             if !timer_first {
                                 // child return first
@@ -183,7 +573,8 @@ impl Singleton {
                                 // This implemention is not guaranteed to work properly, but it indead
                                 // solves the increasing rusage problem. However, it also causes a
                                 // decreasing rusage in the test of problem judger. Further fixes are
-                                // required.
+                                // required. The cgroup backend (see `ResourceBackend::Cgroup`)
+                                // measures memory without this problem.
                                 ru.ru_maxrss -= max_rss_before;
                                 Ok(ru)
                             } else {
@@ -198,35 +589,60 @@ impl Singleton {
                 }
             }
         }?;
+        let scratch_full = scratch.is_some_and(PreparedScratch::full);
+        let instructions = counter
+            .and_then(sigsafe::read_counter)
+            .map_or(-1, |n| n as i64);
+        if let Some(fd) = counter {
+            sigsafe::close(fd);
+        }
 
         if !shared.try_set(share_mem::global_shared_t {
             rusage: ru,
             timer_first: if timer_first { 1 } else { 0 },
             status: child_status.map(|a| a.0).unwrap_or(-1),
+            dangerous_syscall,
+            idle_killed: if idle_killed { 1 } else { 0 },
+            nproc_killed: if nproc_killed { 1 } else { 0 },
+            cancelled: if cancelled { 1 } else { 0 },
+            scratch_full: if scratch_full { 1 } else { 0 },
+            tested_pid: 0,
+            exit_time,
+            instructions,
         }) {
             seprintln!("(child) set shared memory error");
             sigsafe::exit(1);
         }
         Ok(())
     }
-    fn exec_parent(
-        &self,
-        child: i32,
-        start: Instant,
-        guard: sigsafe::SigblockGuard,
-        shared: &share_mem::GlobalShared,
-    ) -> anyhow::Result<Termination> {
-        // do something before waiting for child process
-        drop(guard);
-
+    fn switches_user(&self) -> bool {
+        self.uid.is_some() || self.gid.is_some()
+    }
+    /// 检查输出文件的大小是否超出限制。
+    ///
+    /// RLIMIT_FSIZE 被设为限制加一，如果程序忽略了 SIGXFSZ，超出它的写入会失败（EFBIG），
+    /// 此时输出文件的大小恰好比限制多一个字节，仍然视为超出；输出恰好等于限制则不算超出。
+    fn output_exceeded(&self, captured: &MemoryStdio) -> bool {
+        let (super::Lim::Single(limit) | super::Lim::Double(limit, _)) = self.limits.output_memory;
+        let files = [&self.stdout, &self.stderr]
+            .into_iter()
+            .filter_map(|path| std::fs::metadata(path.to_str().ok()?).ok());
+        let captured = [&captured.stdout, &captured.stderr]
+            .into_iter()
+            .filter_map(|file| file.as_ref()?.metadata().ok());
+        files
+            .chain(captured)
+            .any(|meta| meta.is_file() && Memory::from(meta.len()) > limit)
+    }
+    /// return the termination and the time when the tested process exited
+    fn exec_parent(&self, running: &Running) -> anyhow::Result<(Termination, i64)> {
+        let child = running.pid;
         seprintln!("(parent) wait for child process id: {child}");
 
         let (_, child_status) = sigsafe::waitpid(child, 0).context("parent wait child error")?;
 
         seprintln!("(parent) wait done.");
 
-        let real_time = start.elapsed().into();
-
         if !(child_status.exited() && child_status.exitstatus() == 0) {
             bail!(
                 "child (parent) not normally terminated, with status {}",
@@ -237,13 +653,43 @@ impl Singleton {
             rusage,
             timer_first,
             status,
-        } = shared.get().context("get shared error")?;
-        let cpu_time = Elapse::from(rusage.ru_utime) + Elapse::from(rusage.ru_stime);
+            dangerous_syscall,
+            idle_killed,
+            nproc_killed,
+            cancelled,
+            scratch_full,
+            tested_pid: _,
+            exit_time,
+            instructions,
+        } = running.shared.get().context("get shared error")?;
+        if cancelled != 0 {
+            bail!("execution cancelled");
+        }
+        // the processes of a pipeline are not waited in the order they exit,
+        // thus the real time is measured by the child
+        let real_time = Elapse::from((exit_time - running.start).max(0) as u64 / 1_000_000);
+        let mut cpu_time = Elapse::from(rusage.ru_utime) + Elapse::from(rusage.ru_stime);
         // on macos, the maximum resident set size is measured in bytes (see man getrusage)
         #[cfg(target_os = "macos")]
-        let memory = Memory::from(rusage.ru_maxrss as u64);
+        let mut memory = Memory::from(rusage.ru_maxrss as u64);
         #[cfg(not(target_os = "macos"))]
-        let memory = Memory::from((rusage.ru_maxrss * 1024) as u64);
+        let mut memory = Memory::from((rusage.ru_maxrss * 1024) as u64);
+        let mut oom_killed = false;
+        // failed forks under pids.max, see `nproc_killed` for RLIMIT_NPROC
+        let mut pids_exceeded = false;
+        if let Some(cg) = &running.cgroup {
+            cpu_time = cg.cpu_time().context("read cpu.stat")?;
+            // page cache is not counted, see `ResourceBackend::Cgroup`
+            let peak = cg.memory_peak_without_cache().context("read memory.peak")?;
+            memory = memory.max(peak);
+            oom_killed = cg.oom_killed();
+            pids_exceeded = cg.pids_exceeded();
+        }
+
+        let instructions = (instructions >= 0).then_some(instructions as u64);
+        // ignored if the instructions are not counted
+        let inst_tle =
+            matches!((self.limits.instructions, instructions), (Some(max), Some(n)) if n > max);
 
         macro_rules! real_tle {
             () => {
@@ -251,13 +697,24 @@ impl Singleton {
             };
         }
         let child_status = sigsafe::WaitStatus(status);
-        let status: Status = if child_status.exited() {
+        let status: Status = if dangerous_syscall >= 0 {
+            seprintln!("子进程因调用被禁止的系统调用 {dangerous_syscall} 被终止");
+            Status::DangerousSyscall(dangerous_syscall)
+        } else if child_status.exited() {
             seprintln!("子进程正常退出, exit_code = {}", child_status.exitstatus());
             let exit_code = child_status.exitstatus();
-            if !self.limits.real_memory.check(&memory) {
+            if oom_killed || !self.limits.real_memory.check(&memory) {
                 Status::MemoryLimitExceeded
-            } else if timer_first != 0 || real_tle!() {
+            } else if timer_first != 0
+                || real_tle!()
+                || !self.limits.cpu_time.check(&cpu_time)
+                || inst_tle
+            {
                 Status::TimeLimitExceeded
+            } else if self.output_exceeded(&running.captured) || scratch_full != 0 {
+                Status::OutputLimitExceeded
+            } else if nproc_killed != 0 || exit_code != 0 && pids_exceeded {
+                Status::ProcessLimitExceeded(child_status.0)
             } else if exit_code != 0 {
                 Status::RuntimeError(child_status.0)
             } else {
@@ -269,26 +726,68 @@ impl Singleton {
             }
             seprintln!("子进程被信号终止, signal = {}", child_status.termsig());
             let signal = child_status.termsig();
-            if signal == sigsafe::get_sigkill() || signal == sigsafe::get_sigxcpu() || real_tle!() {
+            if signal == sigsafe::get_sigxfsz() {
+                seprintln!("子进程输出超出限制");
+                Status::OutputLimitExceeded
+            } else if oom_killed {
+                seprintln!("子进程因超出 memory.max 被终止");
+                Status::MemoryLimitExceeded
+            } else if idle_killed != 0 {
+                seprintln!("子进程因长时间空闲被终止");
+                Status::IdlenessLimitExceeded
+            } else if nproc_killed != 0 {
+                seprintln!("子进程因创建的进程数超出限制被终止");
+                Status::ProcessLimitExceeded(child_status.0)
+            } else if signal == sigsafe::get_sigkill()
+                || signal == sigsafe::get_sigxcpu()
+                || real_tle!()
+            {
                 seprintln!("子进程被计时线程终止");
                 Status::TimeLimitExceeded
+            } else if scratch_full != 0 {
+                seprintln!("子进程用完了临时空间");
+                Status::OutputLimitExceeded
+            } else if pids_exceeded {
+                seprintln!("子进程创建的进程数超出限制");
+                Status::ProcessLimitExceeded(child_status.0)
             } else {
                 Status::RuntimeError(child_status.0)
             }
         };
         seprintln!("主进程正常结束");
-        Ok(Termination {
-            status,
-            real_time,
-            cpu_time,
-            memory,
-        })
+        Ok((
+            Termination {
+                status,
+                real_time,
+                cpu_time,
+                memory,
+                exit_code: child_status.exited().then(|| child_status.exitstatus()),
+                signal: child_status.signaled().then(|| {
+                    let number = child_status.termsig() as i32;
+                    crate::Signal {
+                        number,
+                        name: sigsafe::signal_name(number).map(String::from),
+                    }
+                }),
+                voluntary_switches: rusage.ru_nvcsw as u64,
+                involuntary_switches: rusage.ru_nivcsw as u64,
+                minor_page_faults: rusage.ru_minflt as u64,
+                major_page_faults: rusage.ru_majflt as u64,
+                instructions,
+                stdout: None,
+                stderr: None,
+            },
+            exit_time,
+        ))
     }
-}
+    /// 启动程序，不等待其结束
+    pub(crate) fn spawn(&self, stdio: &PipeStdio) -> anyhow::Result<Running> {
+        self.limits.validate().context("invalid limitation")?;
+
+        let (mut captured, stdio) =
+            MemoryStdio::create(self, stdio).context("create in-memory stdio")?;
+        let stdio = &stdio;
 
-#[cfg(feature = "exec_sandbox")]
-impl crate::ExecSandBox for Singleton {
-    fn exec_sandbox(&self) -> anyhow::Result<crate::Termination> {
         // flush rust codes' outputs
         std::io::stdout().flush()?;
         std::io::stderr().flush()?;
@@ -307,28 +806,105 @@ impl crate::ExecSandBox for Singleton {
         let args = crate::to_exec_array(self.arguments.clone());
         let env = crate::to_exec_array(self.envs.clone());
 
-        let start = Instant::now(); // record the real time duration of tested process
+        let cgroup = self.cgroup.as_ref().and_then(|parent| {
+            let (super::Lim::Single(max) | super::Lim::Double(_, max)) = self.limits.real_memory;
+            let (super::Lim::Single(nproc) | super::Lim::Double(_, nproc)) = self.limits.nproc;
+            Cgroup::create(parent, max, nproc)
+                .map_err(|e| seprintln!("(parent) cgroup unavailable, fallback to rlimit: {e}"))
+                .ok()
+        });
+
+        let scratch = match self.scratch {
+            Some(size) => Some(PreparedScratch::new(size).context("prepare scratch space")?),
+            None => None,
+        };
+        let root = match (&self.isolation, &scratch) {
+            (Some(isolation), Some(scratch)) => Some(
+                PreparedRoot::new(&isolation.with_scratch(scratch.path()))
+                    .context("prepare isolated root")?,
+            ),
+            (Some(isolation), None) => {
+                Some(PreparedRoot::new(isolation).context("prepare isolated root")?)
+            }
+            (None, _) => None,
+        };
 
         let guard = sigsafe::sigblockall(); // block all signals before forking
         if guard.contains(sigsafe::get_sigchld())? {
             bail!("previous block sigset contains SIGCHLD");
         }
 
-        let shared = share_mem::GlobalShared::init(); // should be freed in exec_parent
+        let shared = share_mem::GlobalShared::init(); // should be freed in wait
+
+        // record the real time duration of tested process
+        let start = sigsafe::monotonic_ns();
 
-        let r = match sigsafe::fork() {
+        match sigsafe::fork() {
             Ok(0) => {
-                let err = self.exec_child(self.exec_path.as_c_str(), &args, &env, guard, shared);
+                let err = self.exec_child(
+                    self.exec_path.as_c_str(),
+                    &args,
+                    &env,
+                    guard,
+                    shared,
+                    Prepared {
+                        cgroup: cgroup.as_ref(),
+                        root: root.as_ref(),
+                        scratch: scratch.as_ref(),
+                        stdio,
+                    },
+                );
                 if let Err(err) = err {
                     seprintln!("(child) errno = {}", err);
                     sigsafe::exit(1);
                 }
                 sigsafe::exit(0);
             }
-            Ok(pid) => self.exec_parent(pid, start, guard, &shared),
-            Err(e) => Err(e.into()),
-        };
-        shared.free();
-        r.context("exec_sandbox error")
+            Ok(pid) => {
+                drop(guard);
+                // the input is held by the child now
+                captured.stdin = None;
+                Ok(Running {
+                    pid,
+                    start,
+                    shared,
+                    cgroup,
+                    _root: root,
+                    _scratch: scratch,
+                    captured,
+                })
+            }
+            Err(e) => {
+                shared.free();
+                Err(e).context("exec_sandbox error")
+            }
+        }
+    }
+    /// 等待 [`Singleton::spawn`] 启动的程序结束，返回其终止信息以及结束的时刻
+    pub(crate) fn wait(&self, running: Running) -> anyhow::Result<(Termination, i64)> {
+        let r = self.exec_parent(&running);
+        running.shared.free();
+        let (mut term, exit_time) = r.context("exec_sandbox error")?;
+
+        let (stdout, stdout_exceeded) = read_captured(running.captured.stdout, self.capture_stdout)
+            .context("read captured stdout")?;
+        let (stderr, stderr_exceeded) = read_captured(running.captured.stderr, self.capture_stderr)
+            .context("read captured stderr")?;
+        if (stdout_exceeded || stderr_exceeded)
+            && matches!(term.status, Status::Ok | Status::RuntimeError(_))
+        {
+            term.status = Status::OutputLimitExceeded;
+        }
+        term.stdout = stdout;
+        term.stderr = stderr;
+        Ok((term, exit_time))
+    }
+}
+
+#[cfg(feature = "exec_sandbox")]
+impl crate::ExecSandBox for Singleton {
+    fn exec_sandbox(&self) -> anyhow::Result<crate::Termination> {
+        let running = self.spawn(&PipeStdio::default())?;
+        Ok(self.wait(running)?.0)
     }
 }
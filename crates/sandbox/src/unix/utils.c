@@ -1,8 +1,20 @@
+#define _GNU_SOURCE
 #include "utils.h"
 
 #include <fcntl.h>
+#include <grp.h>
 #include <string.h>
 #include <sys/mman.h>
+#include <time.h>
+
+#ifdef __linux__
+#include <dirent.h>
+#include <linux/perf_event.h>
+#include <sched.h>
+#include <sys/personality.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#endif
 
 #define MAXLINE 1024 /* max line size */
 
@@ -79,6 +91,7 @@ sigset_t sigblockall() {
   sigaddset(&mask, SIGCHLD);
   sigaddset(&mask, SIGINT);
   sigaddset(&mask, SIGTSTP);
+  sigaddset(&mask, SIGTERM);
 
   // sigfillset(&mask);
   // sigdelset(&mask, SIGTRAP); // enable debugging
@@ -131,6 +144,10 @@ int get_children_rusage(rusage_t *ru) {
     ru->ru_stime = r.ru_stime;
     ru->ru_utime = r.ru_utime;
     ru->ru_maxrss = r.ru_maxrss;
+    ru->ru_minflt = r.ru_minflt;
+    ru->ru_majflt = r.ru_majflt;
+    ru->ru_nvcsw = r.ru_nvcsw;
+    ru->ru_nivcsw = r.ru_nivcsw;
   }
   return rc;
 }
@@ -141,6 +158,10 @@ int get_self_rusage(rusage_t *ru) {
     ru->ru_stime = r.ru_stime;
     ru->ru_utime = r.ru_utime;
     ru->ru_maxrss = r.ru_maxrss;
+    ru->ru_minflt = r.ru_minflt;
+    ru->ru_majflt = r.ru_majflt;
+    ru->ru_nvcsw = r.ru_nvcsw;
+    ru->ru_nivcsw = r.ru_nivcsw;
   }
   return rc;
 }
@@ -158,6 +179,50 @@ void signal_echo_handler(int signo) {
 inline int get_sigchld() { return SIGCHLD; }
 inline int get_sigkill() { return SIGKILL; }
 inline int get_sigxcpu() { return SIGXCPU; }
+inline int get_sigxfsz() { return SIGXFSZ; }
+inline int get_sigterm() { return SIGTERM; }
+
+const char *signal_abbrev(int signo) {
+  switch (signo) {
+#define CASE(name) \
+  case SIG##name:  \
+    return "SIG" #name;
+    CASE(HUP)
+    CASE(INT)
+    CASE(QUIT)
+    CASE(ILL)
+    CASE(TRAP)
+    CASE(ABRT)
+    CASE(BUS)
+    CASE(FPE)
+    CASE(KILL)
+    CASE(USR1)
+    CASE(SEGV)
+    CASE(USR2)
+    CASE(PIPE)
+    CASE(ALRM)
+    CASE(TERM)
+    CASE(CHLD)
+    CASE(CONT)
+    CASE(STOP)
+    CASE(TSTP)
+    CASE(TTIN)
+    CASE(TTOU)
+    CASE(URG)
+    CASE(XCPU)
+    CASE(XFSZ)
+    CASE(VTALRM)
+    CASE(PROF)
+    CASE(WINCH)
+    CASE(SYS)
+#ifdef SIGSTKFLT
+    CASE(STKFLT)
+#endif
+#undef CASE
+    default:
+      return NULL;
+  }
+}
 
 void *signal_echo(int signo) { return signal(signo, signal_echo_handler); }
 
@@ -168,6 +233,211 @@ int wait_rusage(pid_t pid, int *stat_loc, int options, rusage_t *ru) {
     ru->ru_stime = r.ru_stime;
     ru->ru_utime = r.ru_utime;
     ru->ru_maxrss = r.ru_maxrss;
+    ru->ru_minflt = r.ru_minflt;
+    ru->ru_majflt = r.ru_majflt;
+    ru->ru_nvcsw = r.ru_nvcsw;
+    ru->ru_nivcsw = r.ru_nivcsw;
   }
   return rc;
-}
\ No newline at end of file
+}
+
+int wait_exited_nowait(pid_t pid) {
+  siginfo_t info;
+  int rc;
+  do {
+    rc = waitid(P_PID, pid, &info, WEXITED | WNOWAIT);
+  } while (rc < 0 && errno == EINTR);
+  return rc;
+}
+
+static volatile sig_atomic_t cancel_flag = 0;
+
+static void cancel_handler(int signo) {
+  (void)signo;
+  cancel_flag = 1;
+}
+
+int register_cancel_handler() {
+  struct sigaction sa;
+  memset(&sa, 0, sizeof sa);
+  sa.sa_handler = cancel_handler;
+  sigemptyset(&sa.sa_mask);
+  return sigaction(SIGTERM, &sa, NULL);
+}
+
+int cancel_requested() { return cancel_flag; }
+
+int pipe_cloexec(int *fds) {
+#ifdef __linux__
+  return pipe2(fds, O_CLOEXEC);
+#else
+  if (pipe(fds) < 0) return -1;
+  fcntl(fds[0], F_SETFD, FD_CLOEXEC);
+  fcntl(fds[1], F_SETFD, FD_CLOEXEC);
+  return 0;
+#endif
+}
+
+int anon_file() {
+#ifdef __linux__
+  return memfd_create("zroj-sandbox", MFD_CLOEXEC);
+#else
+  errno = ENOSYS;
+  return -1;
+#endif
+}
+
+long long monotonic_ns() {
+  struct timespec ts;
+  clock_gettime(CLOCK_MONOTONIC, &ts);
+  return (long long)ts.tv_sec * 1000000000 + ts.tv_nsec;
+}
+
+void sleep_ms(unsigned long long ms) {
+  struct timespec req = {.tv_sec = ms / 1000, .tv_nsec = ms % 1000 * 1000000};
+  struct timespec rem;
+  while (nanosleep(&req, &rem) < 0 && errno == EINTR) req = rem;
+}
+
+long long process_cputime_ns(pid_t pid) {
+#ifdef __linux__
+  // see CPUCLOCK_SCHED and MAKE_PROCESS_CPUCLOCK in the kernel source
+  clockid_t clock = ((~(clockid_t)pid) << 3) | 2;
+  struct timespec ts;
+  if (clock_gettime(clock, &ts) < 0) return -1;
+  return (long long)ts.tv_sec * 1000000000 + ts.tv_nsec;
+#else
+  errno = ENOSYS;
+  return -1;
+#endif
+}
+
+#ifdef __linux__
+/* the value of the field key (e.g. "Threads:") in a proc status file */
+static long status_field(const char *status, const char *key) {
+  const char *p = strstr(status, key);
+  if (!p) return -1;
+  p += strlen(key);
+  while (*p == ' ' || *p == '\t') p++;
+  long v = 0;
+  for (; *p >= '0' && *p <= '9'; p++) v = v * 10 + (*p - '0');
+  return v;
+}
+
+/* the number of threads of the process named pid in /proc if its real uid is
+ * uid, otherwise 0 */
+static long user_threads(int proc, const char *pid, uid_t uid) {
+  struct stat st;
+  // the owner of /proc/<pid> is the effective uid, which filters most processes
+  if (fstatat(proc, pid, &st, 0) < 0 || st.st_uid != uid) return 0;
+  char path[64], buf[4096];
+  size_t len = strlen(pid);
+  if (len + sizeof("/status") > sizeof(path)) return 0;
+  memcpy(path, pid, len);
+  memcpy(path + len, "/status", sizeof("/status"));
+  int fd = openat(proc, path, O_RDONLY | O_CLOEXEC);
+  // the process may have exited
+  if (fd < 0) return 0;
+  ssize_t n = read(fd, buf, sizeof(buf) - 1);
+  close(fd);
+  if (n <= 0) return 0;
+  buf[n] = 0;
+  if (status_field(buf, "Uid:") != (long)uid) return 0;
+  long threads = status_field(buf, "Threads:");
+  return threads > 0 ? threads : 0;
+}
+#endif
+
+long long count_user_threads(unsigned int uid) {
+#ifdef __linux__
+  int proc = open("/proc", O_RDONLY | O_DIRECTORY | O_CLOEXEC);
+  if (proc < 0) return -1;
+  char buf[4096];
+  long long count = 0;
+  long n;
+  // readdir is not async-signal-safe
+  while ((n = syscall(SYS_getdents64, proc, buf, sizeof(buf))) > 0) {
+    for (long off = 0; off < n;) {
+      struct dirent64 *d = (struct dirent64 *)(buf + off);
+      off += d->d_reclen;
+      if (d->d_name[0] >= '1' && d->d_name[0] <= '9')
+        count += user_threads(proc, d->d_name, uid);
+    }
+  }
+  int saved = errno;
+  close(proc);
+  errno = saved;
+  return n < 0 ? -1 : count;
+#else
+  (void)uid;
+  errno = ENOSYS;
+  return -1;
+#endif
+}
+
+int set_cpu_affinity(const unsigned int *cpus, int len) {
+#ifdef __linux__
+  cpu_set_t set;
+  CPU_ZERO(&set);
+  for (int i = 0; i < len; i++) {
+    if (cpus[i] >= CPU_SETSIZE) {
+      errno = EINVAL;
+      return -1;
+    }
+    CPU_SET(cpus[i], &set);
+  }
+  return sched_setaffinity(0, sizeof(set), &set);
+#else
+  errno = ENOSYS;
+  return -1;
+#endif
+}
+
+int disable_aslr() {
+#ifdef __linux__
+  // 0xffffffff queries the current persona without changing it
+  int persona = personality(0xffffffff);
+  if (persona < 0) return -1;
+  return personality(persona | ADDR_NO_RANDOMIZE) < 0 ? -1 : 0;
+#else
+  errno = ENOSYS;
+  return -1;
+#endif
+}
+
+int set_nice(int nice) { return setpriority(PRIO_PROCESS, 0, nice); }
+
+int open_instruction_counter() {
+#ifdef __linux__
+  struct perf_event_attr attr;
+  memset(&attr, 0, sizeof(attr));
+  attr.size = sizeof(attr);
+  attr.type = PERF_TYPE_HARDWARE;
+  attr.config = PERF_COUNT_HW_INSTRUCTIONS;
+  // the counter of the calling process is disabled, while the ones inherited
+  // by its children are enabled once they call execve
+  attr.disabled = 1;
+  attr.inherit = 1;
+  attr.enable_on_exec = 1;
+  attr.exclude_kernel = 1;
+  attr.exclude_hv = 1;
+  return syscall(SYS_perf_event_open, &attr, 0, -1, -1, PERF_FLAG_FD_CLOEXEC);
+#else
+  errno = ENOSYS;
+  return -1;
+#endif
+}
+
+long long read_counter(int fd) {
+  long long count;
+  if (read(fd, &count, sizeof(count)) != sizeof(count)) return -1;
+  return count;
+}
+
+int drop_privileges(int uid, int gid, const unsigned int *groups, int len) {
+  // supplementary groups must be replaced while we are still privileged
+  if (setgroups(len, (const gid_t *)groups) < 0) return -1;
+  if (gid >= 0 && setgid(gid) < 0) return -1;
+  if (uid >= 0 && setuid(uid) < 0) return -1;
+  return 0;
+}
@@ -15,6 +15,14 @@ typedef struct {
   struct timeval ru_stime;
   /* Maximum resident set size (in kilobytes).  */
   long int ru_maxrss;
+  /* Page faults not requiring I/O.  */
+  long int ru_minflt;
+  /* Page faults requiring I/O.  */
+  long int ru_majflt;
+  /* Voluntary context switches.  */
+  long int ru_nvcsw;
+  /* Involuntary context switches.  */
+  long int ru_nivcsw;
 } rusage_t;
 
 int get_children_rusage(rusage_t *ru);
@@ -24,6 +32,22 @@ typedef struct {
   rusage_t rusage;
   int timer_first;
   int status;
+  // the number of the forbidden syscall, or -1
+  int dangerous_syscall;
+  // whether the tested process is killed for being idle
+  int idle_killed;
+  // whether the tested process is killed for exceeding RLIMIT_NPROC
+  int nproc_killed;
+  // whether the tested process is killed for cancellation
+  int cancelled;
+  // whether the scratch space is used up after the tested process exits
+  int scratch_full;
+  // pid of the running tested process, or 0 if it is not running
+  int tested_pid;
+  // monotonic time (in nanoseconds) when the tested process is reaped
+  long long exit_time;
+  // retired user-space instructions of the tested process, or -1 if not counted
+  long long instructions;
 } global_shared_t;
 
 global_shared_t *init_shared();
@@ -49,7 +73,82 @@ void *signal_echo(int signo);
 int get_sigchld();
 int get_sigkill();
 int get_sigxcpu();
+int get_sigxfsz();
+int get_sigterm();
+// name of a signal (e.g. "SIGSEGV"), or NULL if unknown
+const char *signal_abbrev(int signo);
 
 int wait_rusage(pid_t pid, int *stat_loc, int options, rusage_t *ru);
+// wait for the child to exit without reaping it, thus its pid is not reused
+int wait_exited_nowait(pid_t pid);
+
+// SIGTERM asks the supervisor to kill the tested process, see cancel_requested
+int register_cancel_handler();
+int cancel_requested();
+
+// create a pipe whose ends are closed on exec
+int pipe_cloexec(int *fds);
+// create an anonymous in-memory file which is closed on exec (linux only)
+int anon_file();
+// CLOCK_MONOTONIC in nanoseconds
+long long monotonic_ns();
+// sleep for ms milliseconds, resumed if interrupted
+void sleep_ms(unsigned long long ms);
+// CPU time (in nanoseconds) consumed by all threads of a child process
+long long process_cputime_ns(pid_t pid);
+// the number of threads whose real uid is uid, which is what RLIMIT_NPROC
+// limits (linux only)
+long long count_user_threads(unsigned int uid);
+// pin the calling process to the given CPUs (linux only)
+int set_cpu_affinity(const unsigned int *cpus, int len);
+// disable address space layout randomization for the calling process and the
+// programs it executes afterwards (linux only)
+int disable_aslr();
+// set the nice value of the calling process
+int set_nice(int nice);
+// count the retired user-space instructions of the children forked afterwards
+// (after they call execve), which are added to the counter when they exit.
+// It fails if hardware counters are not available (linux only).
+int open_instruction_counter();
+// read the value of a perf counter, or -1 on error
+long long read_counter(int fd);
+// replace the supplementary groups, then switch to gid and uid (negative means
+// unchanged), which requires privileges
+int drop_privileges(int uid, int gid, const unsigned int *groups, int len);
+
+// seccomp-bpf syscall filter (linux only, see seccomp.c)
+
+#define NOTIF_INTERRUPTED 0
+#define NOTIF_READY 1
+#define NOTIF_CLOSED 2
+
+int syscall_nr_by_name(const char *name);
+int install_syscall_filter(const int *allowed, int len, const char *exec_path,
+                           int sock);
+int socketpair_unix(int *sv);
+int recv_fd(int sock);
+// the listener is ignored if negative, and wait forever if timeout_ms < 0
+int poll_notif(int listener, const sigset_t *mask, int timeout_ms);
+int recv_notif_nr(int listener);
+
+// namespace isolation (linux only, see isolate.c)
+
+typedef struct {
+  const char *path;
+  int writable;
+} bind_mount_t;
+
+// the user namespace is skipped if the root user is going to switch to another
+// user, which is not mapped in the new user namespace
+int enter_user_pid_ns(int switch_user);
+// the bounding, ambient and inheritable capability sets are cleared as well,
+// thus the executed program can not remount the binds
+int setup_isolated_root(const char *root, const bind_mount_t *binds, int len,
+                        const char *workdir);
+// enter a new mount namespace, mount a tmpfs with options on dir and change
+// the working directory to it
+int mount_scratch(const char *dir, const char *options);
+// whether the space or inodes of the tmpfs mounted on dir is used up
+int scratch_full(const char *dir);
 
 #endif
\ No newline at end of file
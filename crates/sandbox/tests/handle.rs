@@ -0,0 +1,63 @@
+#![cfg(feature = "async")]
+
+use sandbox::{unix::SingletonConfig, Status};
+use std::{process::Command, time::Duration};
+
+fn get_exec_path(name: &str) -> String {
+    let r = Command::new("which")
+        .arg(name)
+        .output()
+        .expect("execute which error");
+    String::from_utf8(r.stdout)
+        .expect("decode utf8 error")
+        .trim()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_handle_wait() -> anyhow::Result<()> {
+    let handle = SingletonConfig::new(get_exec_path("ls"))
+        .push_args(["ls"])
+        .build()
+        .spawn_async()?;
+    let term = handle.wait().await?;
+    assert_eq!(term.status, Status::Ok);
+    assert!(handle.usage().is_none());
+    assert!(handle.wait().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_handle_kill() -> anyhow::Result<()> {
+    let handle = SingletonConfig::new(get_exec_path("sleep"))
+        .push_args(["sleep", "10"])
+        .build()
+        .spawn_async()?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let usage = handle.usage().expect("running");
+    assert!(usage.real_time.ms() >= 200);
+
+    let start = std::time::Instant::now();
+    handle.kill()?;
+    assert!(handle.wait().await.is_err());
+    assert!(start.elapsed() < Duration::from_secs(2));
+    // killing a finished program is ignored
+    handle.kill()?;
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(target_os = "linux")]
+async fn test_handle_usage() -> anyhow::Result<()> {
+    let handle = SingletonConfig::new(get_exec_path("sh"))
+        .push_args(["sh", "-c", "while true; do :; done"])
+        .build()
+        .spawn_async()?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let usage = handle.usage().expect("running");
+    assert!(usage.cpu_time.ms() > 0);
+    assert!(usage.memory.byte() > 0);
+    handle.kill()?;
+    assert!(handle.wait().await.is_err());
+    Ok(())
+}
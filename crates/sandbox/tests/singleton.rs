@@ -2,8 +2,11 @@
 
 use anyhow::Context;
 use sandbox::{
-    unix::{Lim, Limitation, SingletonConfig},
-    ExecSandBox, Status,
+    unix::{
+        EnvPolicy, Isolation, Lim, Limitation, Pipeline, ResourceBackend, SingletonConfig,
+        SyscallPolicy,
+    },
+    ExecSandBox, Memory, Status,
 };
 use std::{io::Write, process::Command};
 use tempfile::tempdir;
@@ -66,6 +69,45 @@ fn test_env() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_env_policy() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = dir.path().join("output.txt");
+    let env_path = get_exec_path("env");
+
+    let term = SingletonConfig::new(&env_path)
+        .push_args(["env"])
+        .inherit_envs(["PATH", "ZROJ_SANDBOX_NOT_EXIST"])
+        .env_policy(EnvPolicy::default().var("A", "b"))
+        .push_envs(["A=c"])
+        .stdout(output.to_str().unwrap())
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    let envs = std::fs::read_to_string(&output)?;
+    let path = std::env::var("PATH")?;
+    assert_eq!(envs, format!("PATH={path}\nA=c\n"));
+    Ok(())
+}
+
+#[test]
+fn test_env_policy_serde() -> anyhow::Result<()> {
+    // inherited variables are resolved before serializing
+    let policy = EnvPolicy::default().inherit(["PATH"]).var("A", "b");
+    let json = serde_json::to_string(&policy)?;
+    let path = std::env::var("PATH")?;
+    assert_eq!(
+        json,
+        serde_json::to_string(&[format!("PATH={path}"), "A=b".into()])?
+    );
+    let policy: EnvPolicy = serde_json::from_str(&json)?;
+    assert_eq!(policy.resolve(), [format!("PATH={path}"), "A=b".into()]);
+
+    let policy: EnvPolicy = serde_json::from_str(r#"{"inherit": ["PATH"], "vars": [["A", "b"]]}"#)?;
+    assert_eq!(policy.resolve(), [format!("PATH={path}"), "A=b".into()]);
+    Ok(())
+}
+
 #[test]
 fn test_loop() -> anyhow::Result<()> {
     let dir = tempfile::TempDir::new().unwrap();
@@ -130,6 +172,8 @@ fn test_cat_stdio() -> anyhow::Result<()> {
             stack_memory: Lim::Single((2 << 30).into()),
             output_memory: Lim::Single((64 << 20).into()),
             fileno: Lim::Single(10),
+            nproc: Lim::Single(1),
+            instructions: None,
         })
         .build();
 
@@ -144,6 +188,33 @@ fn test_cat_stdio() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_memory_stdio() -> anyhow::Result<()> {
+    let sh = get_exec_path("sh");
+    let term = SingletonConfig::new(&sh)
+        .push_args(["sh", "-c", "cat; echo err >&2"])
+        .stdin_bytes("hello\n world")
+        .capture_stdout(Memory::from(1 << 20))
+        .capture_stderr(Memory::from(1 << 20))
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    assert_eq!(term.stdout.as_deref(), Some(&b"hello\n world"[..]));
+    assert_eq!(term.stderr.as_deref(), Some(&b"err\n"[..]));
+
+    // the captured output is truncated to the cap
+    let term = SingletonConfig::new(&sh)
+        .push_args(["sh", "-c", "cat"])
+        .stdin_bytes("hello\n world")
+        .capture_stdout(Memory::from(5))
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::OutputLimitExceeded);
+    assert_eq!(term.stdout.as_deref(), Some(&b"hello"[..]));
+    assert_eq!(term.stderr, None);
+    Ok(())
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_gcc_linux() -> anyhow::Result<()> {
@@ -170,6 +241,8 @@ fn test_gcc_linux() -> anyhow::Result<()> {
             stack_memory: Lim::Single((2 << 30).into()),
             output_memory: Lim::Single((64 << 20).into()),
             fileno: Lim::Single(30),
+            nproc: Lim::Single(1024),
+            instructions: None,
         })
         .build();
     let term = s.exec_sandbox()?;
@@ -177,3 +250,657 @@ fn test_gcc_linux() -> anyhow::Result<()> {
     dbg!(&term);
     Ok(())
 }
+
+fn compile_c(dir: &tempfile::TempDir, source: &str) -> String {
+    let main_path = dir.path().join("main.c");
+    let exec_path = dir.path().join("main");
+    std::fs::write(&main_path, source).unwrap();
+    let r = Command::new("gcc")
+        .arg(&main_path)
+        .arg("-o")
+        .arg(&exec_path)
+        .status()
+        .unwrap();
+    assert!(exec_path.is_file() && r.success());
+    exec_path.to_str().unwrap().to_string()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_syscall_allowed() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <stdio.h>
+        int main() { int a, b; scanf("%d%d", &a, &b); printf("%d\n", a + b); }"#,
+    );
+    let input = dir.path().join("input.txt");
+    std::fs::write(&input, "1 2")?;
+    let term = SingletonConfig::new(&exec_path)
+        .stdin(input.to_str().unwrap())
+        .syscall_policy(SyscallPolicy::native())
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_dangerous_syscall() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <sys/socket.h>
+        int main() { socket(AF_INET, SOCK_STREAM, 0); return 0; }"#,
+    );
+    let term = SingletonConfig::new(&exec_path)
+        .syscall_policy(SyscallPolicy::native())
+        .build()
+        .exec_sandbox()?;
+    assert!(matches!(term.status, Status::DangerousSyscall(_)));
+
+    // without syscall filter
+    let term = SingletonConfig::new(&exec_path).build().exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    Ok(())
+}
+
+#[test]
+fn test_output_limit() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = dir.path().join("output.txt");
+    let yes_path = get_exec_path("yes");
+    let term = SingletonConfig::new(&yes_path)
+        .push_args(["yes"])
+        .stdout(output.to_str().unwrap())
+        .set_limits(|mut l| {
+            l.output_memory = Lim::Single(Memory::from(64 << 10));
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::OutputLimitExceeded);
+
+    // the program ignores SIGXFSZ and exits normally
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <signal.h>
+        #include <stdio.h>
+        int main() {
+            signal(SIGXFSZ, SIG_IGN);
+            for (int i = 0; i < 100000; i++) printf("%d\n", i);
+            return 0;
+        }"#,
+    );
+    let term = SingletonConfig::new(&exec_path)
+        .stdout(output.to_str().unwrap())
+        .set_limits(|mut l| {
+            l.output_memory = Lim::Single(Memory::from(64 << 10));
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::OutputLimitExceeded);
+
+    // output exactly the limit, then one more byte
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <stdio.h>
+        #include <stdlib.h>
+        int main(int argc, char **argv) {
+            for (long i = atol(argv[1]); i > 0; i--) putchar('x');
+            return 0;
+        }"#,
+    );
+    for (len, status) in [
+        (64 << 10, Status::Ok),
+        ((64 << 10) + 1, Status::OutputLimitExceeded),
+    ] {
+        let term = SingletonConfig::new(&exec_path)
+            .push_args(["main", &len.to_string()])
+            .stdout(output.to_str().unwrap())
+            .set_limits(|mut l| {
+                l.output_memory = Lim::Single(Memory::from(64 << 10));
+                l
+            })
+            .build()
+            .exec_sandbox()?;
+        assert_eq!(term.status, status, "output {len} bytes");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_millisecond_limit() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let exec_path = compile_c(&dir, "int main() { for(;;); }");
+    // CPU time limit below one second
+    let term = SingletonConfig::new(&exec_path)
+        .set_limits(|mut l| {
+            l.cpu_time = Lim::Single(300.into());
+            l.real_time = Lim::Single(5000.into());
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::TimeLimitExceeded);
+    assert!(term.cpu_time.ms() >= 300 && term.cpu_time.ms() < 1000);
+
+    // real time limit is not truncated to seconds
+    let sleep_path = get_exec_path("sleep");
+    let term = SingletonConfig::new(&sleep_path)
+        .push_args(["sleep", "0.5"])
+        .set_limits(|mut l| {
+            l.real_time = Lim::Single(1500.into());
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+
+    let term = SingletonConfig::new(&sleep_path)
+        .push_args(["sleep", "5"])
+        .set_limits(|mut l| {
+            l.real_time = Lim::Single(200.into());
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::TimeLimitExceeded);
+    assert!(term.real_time.ms() < 1000);
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_nproc_limit() -> anyhow::Result<()> {
+    let Some(cgroup) = delegated_cgroup("test_nproc_limit") else {
+        eprintln!("ZROJ_TEST_CGROUP is not set, skip");
+        return Ok(());
+    };
+    let dir = tempfile::TempDir::new().unwrap();
+    // exit with 1 if fork fails
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <unistd.h>
+        int main() {
+            for (int i = 0; i < 16; i++) {
+                pid_t pid = fork();
+                if (pid < 0) return 1;
+                if (pid == 0) { sleep(1); return 0; }
+            }
+            return 0;
+        }"#,
+    );
+    let term = SingletonConfig::new(&exec_path)
+        .isolate(Isolation::default().readonly(&exec_path))
+        .resource_backend(ResourceBackend::Cgroup(cgroup.clone()))
+        .set_limits(|mut l| {
+            l.nproc = Lim::Single(4);
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert!(
+        matches!(term.status, Status::ProcessLimitExceeded(_)),
+        "{term:?}"
+    );
+    std::fs::remove_dir(&cgroup)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_nproc_limit_rlimit() -> anyhow::Result<()> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    // RLIMIT_NPROC is only set when switching to a dedicated user, which requires root
+    if std::fs::metadata("/proc/self")?.uid() != 0 {
+        return Ok(());
+    }
+    let dir = tempfile::TempDir::new().unwrap();
+    // the program must be accessible by the unprivileged user
+    std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o755))?;
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <unistd.h>
+        int main() {
+            for (int i = 0; i < 16; i++) {
+                pid_t pid = fork();
+                if (pid < 0) return 1;
+                if (pid == 0) { sleep(1); return 0; }
+            }
+            return 0;
+        }"#,
+    );
+    let term = SingletonConfig::new(&exec_path)
+        .user(65534, 65534)
+        .set_limits(|mut l| {
+            l.nproc = Lim::Single(4);
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert!(
+        matches!(term.status, Status::ProcessLimitExceeded(_)),
+        "{term:?}"
+    );
+
+    // below the limit
+    let term = SingletonConfig::new(&exec_path)
+        .user(65534, 65534)
+        .set_limits(|mut l| {
+            l.nproc = Lim::Single(32);
+            l
+        })
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_stable_timing() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = dir.path().join("output.txt");
+    let cat_path = get_exec_path("cat");
+
+    let term = SingletonConfig::new(&cat_path)
+        .push_args([
+            "cat",
+            "/proc/self/status",
+            "/proc/self/personality",
+            "/proc/self/stat",
+        ])
+        .stdout(output.to_str().unwrap())
+        .cpus([0])
+        .disable_aslr()
+        .nice(5)
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    let content = std::fs::read_to_string(&output)?;
+    let mut lines = content.lines().rev();
+    // the 19th field of /proc/self/stat is the nice value
+    let stat = lines.next().context("stat")?;
+    let nice = stat
+        .rsplit_once(')')
+        .context("comm")?
+        .1
+        .split_whitespace()
+        .nth(16);
+    assert_eq!(nice, Some("5"));
+    // ADDR_NO_RANDOMIZE = 0x0040000
+    let personality = u32::from_str_radix(lines.next().context("personality")?, 16)?;
+    assert_ne!(personality & 0x0040000, 0);
+    assert!(content.lines().any(|l| l == "Cpus_allowed_list:\t0"));
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_idle_limit() -> anyhow::Result<()> {
+    let sleep_path = get_exec_path("sleep");
+    let term = SingletonConfig::new(&sleep_path)
+        .push_args(["sleep", "10"])
+        .idle_limit(200.into())
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::IdlenessLimitExceeded);
+    assert!(term.real_time.ms() < 1000);
+
+    // programs using CPU are not affected
+    let sh_path = get_exec_path("sh");
+    let term = SingletonConfig::new(&sh_path)
+        .push_args([
+            "sh",
+            "-c",
+            "i=0; while [ $i -lt 100000 ]; do i=$((i+1)); done",
+        ])
+        .idle_limit(100.into())
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    Ok(())
+}
+
+#[test]
+fn test_exit_code_and_signal() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <stdlib.h>
+        int main(int argc, char **argv) {
+            if (argc > 1) return atoi(argv[1]);
+            *(volatile int *)0 = 1;
+            return 0;
+        }"#,
+    );
+    let term = SingletonConfig::new(&exec_path)
+        .push_args(["main", "3"])
+        .build()
+        .exec_sandbox()?;
+    assert!(matches!(term.status, Status::RuntimeError(_)));
+    assert_eq!(term.exit_code, Some(3));
+    assert!(term.signal.is_none());
+
+    let term = SingletonConfig::new(&exec_path)
+        .push_args(["main"])
+        .build()
+        .exec_sandbox()?;
+    assert!(matches!(term.status, Status::RuntimeError(_)));
+    assert_eq!(term.exit_code, None);
+    let signal = term.signal.unwrap();
+    assert_eq!(signal.name.as_deref(), Some("SIGSEGV"));
+    assert_eq!(signal.to_string(), "SIGSEGV");
+    assert!(term.minor_page_faults > 0);
+    Ok(())
+}
+
+#[test]
+fn test_cgroup_fallback() -> anyhow::Result<()> {
+    let ls_path = get_exec_path("ls");
+    // cgroup is not available, fallback to rlimit
+    let term = SingletonConfig::new(ls_path)
+        .push_args(["ls", "-l", "."])
+        .resource_backend(ResourceBackend::Cgroup("/nonexistent".into()))
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    assert!(term.memory > Memory::from(0));
+    Ok(())
+}
+
+/// 在委派给当前用户的 cgroup v2 目录（通过环境变量 `ZROJ_TEST_CGROUP` 指定）下
+/// 为每个测试创建一个子目录，未指定时返回 None
+fn delegated_cgroup(name: &str) -> Option<String> {
+    let root = std::path::PathBuf::from(std::env::var("ZROJ_TEST_CGROUP").ok()?);
+    std::fs::write(root.join("cgroup.subtree_control"), "+memory +pids").ok()?;
+    let dir = root.join(name);
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.to_str()?.to_string())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cgroup() -> anyhow::Result<()> {
+    let Some(cgroup) = delegated_cgroup("test_cgroup") else {
+        eprintln!("ZROJ_TEST_CGROUP is not set, skip");
+        return Ok(());
+    };
+    let dir = tempfile::TempDir::new().unwrap();
+    // write `argv[2]` MB to stdout, or allocate `argv[2]` MB of memory
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <stdio.h>
+        #include <stdlib.h>
+        #include <string.h>
+        int main(int argc, char **argv) {
+            size_t n = (size_t)atoi(argv[2]) << 20;
+            if (argv[1][0] == 'w') {
+                char *buf = malloc(1 << 20);
+                memset(buf, 'a', 1 << 20);
+                for (size_t i = 0; i < n; i += 1 << 20) fwrite(buf, 1, 1 << 20, stdout);
+            } else {
+                char *p = malloc(n);
+                memset(p, 1, n);
+            }
+            return 0;
+        }"#,
+    );
+    let output = dir.path().join("output.txt");
+    let run = |args: [&str; 3]| {
+        SingletonConfig::new(&exec_path)
+            .push_args(args)
+            .stdout(output.to_str().unwrap())
+            .resource_backend(ResourceBackend::Cgroup(cgroup.clone()))
+            .set_limits(|mut l| {
+                l.real_memory = Lim::Single(Memory::from_mb(32));
+                l
+            })
+            .build()
+            .exec_sandbox()
+    };
+
+    // the page cache of the output file is not counted
+    let term = run(["main", "w", "64"])?;
+    assert_eq!(term.status, Status::Ok, "{term:?}");
+    assert!(term.memory < Memory::from_mb(32), "{term:?}");
+
+    let term = run(["main", "m", "64"])?;
+    assert_eq!(term.status, Status::MemoryLimitExceeded, "{term:?}");
+
+    // the child cgroups are removed after running
+    let left = std::fs::read_dir(&cgroup)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("zroj-sandbox-"))
+        .count();
+    assert_eq!(left, 0);
+    std::fs::remove_dir(&cgroup)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_isolation() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let secret = tempfile::TempDir::new().unwrap();
+    let secret_path = secret.path().join("answer.txt");
+    std::fs::write(&secret_path, "42")?;
+    // exit with 0 iff the secret file is not accessible and it is the init process
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <stdio.h>
+        #include <unistd.h>
+        int main(int argc, char **argv) {
+            FILE *f = fopen(argv[1], "r");
+            return f == NULL && getpid() == 1 ? 0 : 1;
+        }"#,
+    );
+    let secret_path = secret_path.to_str().unwrap();
+
+    let term = SingletonConfig::new(&exec_path)
+        .push_args(["main", secret_path])
+        .isolate(Isolation::default().readonly(&exec_path))
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+
+    // without isolation
+    let term = SingletonConfig::new(&exec_path)
+        .push_args(["main", secret_path])
+        .build()
+        .exec_sandbox()?;
+    assert!(matches!(term.status, Status::RuntimeError(_)));
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_isolation_remount() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let ro = tempfile::TempDir::new().unwrap();
+    // exit with 0 iff the read-only bind can not be made writable,
+    // neither directly nor in a nested user namespace
+    let exec_path = compile_c(
+        &dir,
+        r#"#define _GNU_SOURCE
+        #include <fcntl.h>
+        #include <sched.h>
+        #include <stdio.h>
+        #include <sys/mount.h>
+        int main(int argc, char **argv) {
+            char path[4096];
+            snprintf(path, sizeof(path), "%s/pwned", argv[1]);
+            if (mount(NULL, argv[1], NULL, MS_BIND | MS_REMOUNT, NULL) == 0) return 1;
+            if (umount2(argv[1], MNT_DETACH) == 0) return 2;
+            if (unshare(CLONE_NEWUSER | CLONE_NEWNS) == 0 &&
+                mount(NULL, argv[1], NULL, MS_BIND | MS_REMOUNT, NULL) == 0)
+                return 3;
+            if (open(path, O_WRONLY | O_CREAT, 0644) >= 0) return 4;
+            return 0;
+        }"#,
+    );
+    let ro_path = ro.path().to_str().unwrap();
+
+    let term = SingletonConfig::new(&exec_path)
+        .push_args(["main", ro_path])
+        .isolate(Isolation::default().readonly(&exec_path).readonly(ro_path))
+        .build()
+        .exec_sandbox()?;
+    assert_eq!(term.status, Status::Ok);
+    assert!(!ro.path().join("pwned").exists());
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_drop_privileges() -> anyhow::Result<()> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    // switching user requires root
+    if std::fs::metadata("/proc/self")?.uid() != 0 {
+        return Ok(());
+    }
+    let dir = tempfile::TempDir::new().unwrap();
+    // the program must be accessible by the unprivileged user
+    std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o755))?;
+    let secret = tempfile::TempDir::new().unwrap();
+    let secret_path = secret.path().join("answer.txt");
+    std::fs::set_permissions(secret.path(), std::fs::Permissions::from_mode(0o700))?;
+    std::fs::write(&secret_path, "42")?;
+    let exec_path = compile_c(
+        &dir,
+        r#"#include <stdio.h>
+        #include <unistd.h>
+        int main(int argc, char **argv) {
+            gid_t groups[4];
+            int ngroups = getgroups(4, groups);
+            printf("%d %d %d %d\n", getuid(), getgid(), ngroups, fopen(argv[1], "r") == NULL);
+            return 0;
+        }"#,
+    );
+    let secret_path = secret_path.to_str().unwrap();
+
+    for isolation in [None, Some(Isolation::default().readonly(&exec_path))] {
+        let mut cfg = SingletonConfig::new(&exec_path)
+            .push_args(["main", secret_path])
+            .user(65534, 65534)
+            .groups([65534])
+            .capture_stdout(Memory::from(1024));
+        if let Some(isolation) = isolation {
+            cfg = cfg.isolate(isolation);
+        }
+        let term = cfg.build().exec_sandbox()?;
+        assert_eq!(term.status, Status::Ok);
+        assert_eq!(term.stdout.as_deref(), Some(&b"65534 65534 1 1\n"[..]));
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_instructions() -> anyhow::Result<()> {
+    let sh = get_exec_path("sh");
+    let run = |inst: Option<u64>| {
+        SingletonConfig::new(&sh)
+            .push_args([
+                "sh",
+                "-c",
+                "i=0; while [ $i -lt 10000 ]; do i=$((i+1)); done",
+            ])
+            .count_instructions()
+            .set_limits(|mut l| {
+                l.instructions = inst;
+                l
+            })
+            .build()
+            .exec_sandbox()
+    };
+    let term = run(None)?;
+    assert_eq!(term.status, Status::Ok);
+    match term.instructions {
+        Some(n) => {
+            assert!(n > 1_000_000);
+            assert_eq!(run(Some(n / 2))?.status, Status::TimeLimitExceeded);
+        }
+        // hardware counters are not available, thus the budget is ignored
+        None => assert_eq!(run(Some(1))?.status, Status::Ok),
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_scratch() -> anyhow::Result<()> {
+    let sh = get_exec_path("sh");
+    let run = |script: &str| {
+        SingletonConfig::new(&sh)
+            .push_args(["sh", "-c", script])
+            .isolate(Isolation::default())
+            .scratch(Memory::from_mb(1))
+            .build()
+            .exec_sandbox()
+    };
+    // the root is read-only, thus the file is written in the scratch space
+    let term = run("head -c 100000 /dev/zero > a && test -s a")?;
+    assert_eq!(term.status, Status::Ok);
+
+    let term = run("head -c 2000000 /dev/zero > a")?;
+    assert_eq!(term.status, Status::OutputLimitExceeded);
+
+    let term = run("for i in $(seq 2000); do : > f$i || exit 1; done")?;
+    assert_eq!(term.status, Status::OutputLimitExceeded);
+    Ok(())
+}
+
+#[test]
+fn test_pipeline() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new().unwrap();
+    let interactor = compile_c(
+        &dir,
+        r#"#include <stdio.h>
+        #include <unistd.h>
+        int main() {
+            int x = 0;
+            printf("41\n");
+            fflush(stdout);
+            if (scanf("%d", &x) != 1) {
+                usleep(200000);
+                return 1;
+            }
+            return x == 42 ? 0 : 1;
+        }"#,
+    );
+    let dir2 = tempfile::TempDir::new().unwrap();
+    let solution = compile_c(
+        &dir2,
+        r#"#include <stdio.h>
+        int main() { int x; scanf("%d", &x); printf("%d\n", x + 1); return 0; }"#,
+    );
+    let term = Pipeline::new(vec![
+        SingletonConfig::new(&interactor).build(),
+        SingletonConfig::new(&solution).build(),
+    ])
+    .pipe(0, 1)
+    .pipe(1, 0)
+    .exec_pipeline()?;
+    assert_eq!(term.terminations[0].status, Status::Ok);
+    assert_eq!(term.terminations[1].status, Status::Ok);
+
+    // the solution exits without any output
+    let term = Pipeline::new(vec![
+        SingletonConfig::new(&interactor).build(),
+        SingletonConfig::new(get_exec_path("true")).build(),
+    ])
+    .pipe(0, 1)
+    .pipe(1, 0)
+    .exec_pipeline()?;
+    assert!(matches!(
+        term.terminations[0].status,
+        Status::RuntimeError(_)
+    ));
+    assert_eq!(term.terminations[1].status, Status::Ok);
+    assert_eq!(term.first, 1);
+    Ok(())
+}
@@ -0,0 +1,304 @@
+//! 检查当前主机是否能够安全地评测。
+//!
+//! 沙盒依赖的内核特性（seccomp、user namespace、cgroup v2 委派、rlimit、切换用户等）在不同的主机上
+//! 不一定可用，而沙盒在特性不可用时可能会退回到较弱的限制方式。这里用当前可执行文件作为
+//! 探测程序（见 [`Canary`]），在沙盒中运行并检查是否得到预期的 [`Status`]。
+
+use clap::{
+    builder::styling::{AnsiColor, Color, Style},
+    ValueEnum,
+};
+use sandbox::{
+    unix::{Isolation, Lim, Limitation, ResourceBackend, SingletonConfig, SyscallPolicy},
+    Elapse, ExecSandBox, Memory, Status,
+};
+use serde::Serialize;
+use std::{io::Write, path::Path};
+
+/// 用于探测沙盒限制的程序，作为 `zroj-sandbox canary <kind>` 运行。
+///
+/// 除了 [`Canary::Loop`] 以外，所有探测程序都会在有限的资源内自行结束，
+/// 以免在限制不起作用时影响主机。
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Canary {
+    /// 立即正常退出
+    Ok,
+    /// 死循环
+    Loop,
+    /// 逐步申请并使用 256MiB 内存
+    Memory,
+    /// 创建至多 64 个子进程，创建失败时以 1 退出
+    Fork,
+    /// 向标准输出写入至多 64MiB
+    Output,
+    /// 休眠 500ms，是 [`Canary::Fork`] 创建的子进程
+    Idle,
+}
+
+/// 运行探测程序
+pub fn canary(kind: Canary) -> anyhow::Result<()> {
+    match kind {
+        Canary::Ok => {}
+        Canary::Loop => loop {
+            std::hint::black_box(());
+        },
+        Canary::Memory => {
+            let mut chunks = Vec::new();
+            for _ in 0..256 {
+                // non-zero bytes, so that the pages are actually touched
+                chunks.push(vec![1u8; 1 << 20]);
+            }
+            std::hint::black_box(&chunks);
+        }
+        Canary::Fork => {
+            let exe = std::env::current_exe()?;
+            let mut children = Vec::new();
+            for _ in 0..64 {
+                match std::process::Command::new(&exe)
+                    .args(["canary", "idle"])
+                    .spawn()
+                {
+                    Ok(child) => children.push(child),
+                    Err(_) => std::process::exit(1),
+                }
+            }
+            for mut child in children {
+                child.wait()?;
+            }
+        }
+        Canary::Output => {
+            let buf = vec![b'x'; 1 << 20];
+            let mut stdout = std::io::stdout().lock();
+            for _ in 0..64 {
+                stdout.write_all(&buf)?;
+            }
+        }
+        Canary::Idle => std::thread::sleep(std::time::Duration::from_millis(500)),
+    }
+    Ok(())
+}
+
+/// 一项检查的结果
+#[derive(Serialize, Debug)]
+pub struct Check {
+    /// 检查的名称
+    pub name: &'static str,
+    /// 使用的资源限制方式，为 None 表示与之无关
+    pub backend: Option<&'static str>,
+    /// 预期的状态
+    pub expected: &'static str,
+    /// 实际的状态，为 None 表示沙盒执行出错
+    pub status: Option<Status>,
+    /// 沙盒执行的错误信息
+    pub error: Option<String>,
+    /// 是否符合预期
+    pub passed: bool,
+}
+
+struct Probe {
+    name: &'static str,
+    kind: Canary,
+    expected: &'static str,
+    expect: fn(&Status) -> bool,
+    /// 设置限制，参数中的路径是探测程序的路径
+    setup: fn(SingletonConfig, &str) -> SingletonConfig,
+}
+
+/// 切换用户的检查使用的 uid 和 gid（通常是 nobody）
+const NOBODY: u32 = 65534;
+
+/// 与资源限制方式无关的检查
+const CAPABILITY_PROBES: &[Probe] = &[
+    Probe {
+        name: "seccomp",
+        kind: Canary::Ok,
+        expected: "DangerousSyscall",
+        expect: |s| matches!(s, Status::DangerousSyscall(_)),
+        setup: |cfg, _| cfg.syscall_policy(SyscallPolicy::AllowList(Vec::new())),
+    },
+    Probe {
+        name: "namespace",
+        kind: Canary::Ok,
+        expected: "Ok",
+        expect: |s| s.ok(),
+        setup: |cfg, exe| cfg.isolate(Isolation::default().readonly(exe)),
+    },
+    // fails if not running as root, thus programs can access all files of the judger
+    Probe {
+        name: "drop privileges",
+        kind: Canary::Ok,
+        expected: "Ok",
+        expect: |s| s.ok(),
+        setup: |cfg, _| cfg.user(NOBODY, NOBODY),
+    },
+];
+
+/// 对每种资源限制方式都要做的检查
+const LIMIT_PROBES: &[Probe] = &[
+    Probe {
+        name: "infinite loop",
+        kind: Canary::Loop,
+        expected: "TimeLimitExceeded",
+        expect: |s| matches!(s, Status::TimeLimitExceeded),
+        setup: |cfg, _| {
+            cfg.set_limits(|mut l| {
+                l.cpu_time = Lim::Single(Elapse::from(500));
+                l.real_time = Lim::Double(Elapse::from(2000), Elapse::from(3000));
+                l
+            })
+        },
+    },
+    Probe {
+        name: "memory hog",
+        kind: Canary::Memory,
+        expected: "MemoryLimitExceeded",
+        expect: |s| matches!(s, Status::MemoryLimitExceeded),
+        setup: |cfg, _| {
+            cfg.set_limits(|mut l| {
+                l.real_memory = Lim::Single(Memory::from_mb(64));
+                l
+            })
+        },
+    },
+    Probe {
+        name: "big output",
+        kind: Canary::Output,
+        expected: "OutputLimitExceeded",
+        expect: |s| matches!(s, Status::OutputLimitExceeded),
+        setup: |cfg, _| {
+            cfg.set_limits(|mut l| {
+                l.output_memory = Lim::Single(Memory::from_mb(4));
+                l
+            })
+        },
+    },
+];
+
+/// `RLIMIT_NPROC` 只在切换用户时生效，因此以 nobody 运行
+const FORK_PROBE: Probe = Probe {
+    name: "fork bomb",
+    kind: Canary::Fork,
+    expected: "ProcessLimitExceeded",
+    expect: |s| matches!(s, Status::ProcessLimitExceeded(_)),
+    setup: |cfg, _| {
+        cfg.user(NOBODY, NOBODY).set_limits(|mut l| {
+            l.nproc = Lim::Single(16);
+            l
+        })
+    },
+};
+
+fn run_probe(exe: &str, probe: &Probe, backend: Option<(&'static str, &ResourceBackend)>) -> Check {
+    let output =
+        std::env::temp_dir().join(format!("zroj-sandbox-doctor-{}.out", std::process::id()));
+    let cfg = SingletonConfig::new(exe)
+        .push_args(["zroj-sandbox", "canary"])
+        .push_args(
+            probe
+                .kind
+                .to_possible_value()
+                .as_ref()
+                .map(|v| v.get_name()),
+        )
+        .stdout(output.to_string_lossy())
+        .set_limits(|_| Limitation {
+            real_time: Lim::Double(Elapse::from_sec(5), Elapse::from_sec(10)),
+            cpu_time: Lim::Single(Elapse::from_sec(5)),
+            ..Limitation::default()
+        });
+    let cfg = match backend {
+        Some((_, backend)) => cfg.resource_backend(backend.clone()),
+        None => cfg,
+    };
+    let term = (probe.setup)(cfg, exe).build().exec_sandbox();
+    let _ = std::fs::remove_file(&output);
+
+    let (status, error) = match term {
+        Ok(term) => (Some(term.status), None),
+        Err(e) => (None, Some(format!("{e:#}"))),
+    };
+    Check {
+        name: probe.name,
+        backend: backend.map(|(name, _)| name),
+        expected: probe.expected,
+        passed: status.as_ref().is_some_and(probe.expect),
+        status,
+        error,
+    }
+}
+
+/// 以 `exe`（即 `zroj-sandbox` 自身）作为探测程序运行所有检查。
+///
+/// 资源限制的检查会使用 rlimit 运行一次，如果给出了 `cgroup` 还会使用该 cgroup 运行一次。
+pub fn doctor(exe: &Path, cgroup: Option<String>) -> Vec<Check> {
+    let exe = exe.to_string_lossy();
+    let mut checks: Vec<Check> = CAPABILITY_PROBES
+        .iter()
+        .map(|probe| run_probe(&exe, probe, None))
+        .collect();
+
+    let mut backends = vec![("rlimit", ResourceBackend::Rlimit)];
+    if let Some(path) = cgroup {
+        backends.push(("cgroup", ResourceBackend::Cgroup(path)));
+    }
+    for (name, backend) in &backends {
+        for probe in LIMIT_PROBES.iter().chain([&FORK_PROBE]) {
+            checks.push(run_probe(&exe, probe, Some((name, backend))));
+        }
+    }
+    checks
+}
+
+/// Print the checks as a colored table
+pub fn print_checks(checks: &[Check]) {
+    let header = Style::new()
+        .bold()
+        .fg_color(Some(Color::Ansi(AnsiColor::BrightGreen)));
+    let passed = Style::new()
+        .bold()
+        .fg_color(Some(Color::Ansi(AnsiColor::Green)));
+    let failed = Style::new()
+        .bold()
+        .fg_color(Some(Color::Ansi(AnsiColor::Red)));
+
+    let actual: Vec<String> = checks
+        .iter()
+        .map(|c| match (&c.status, &c.error) {
+            (Some(status), _) => format!("{status:?}"),
+            (None, Some(e)) => format!("error: {e}"),
+            (None, None) => String::new(),
+        })
+        .collect();
+    let w_name = checks
+        .iter()
+        .map(|c| c.name.len())
+        .chain([5])
+        .max()
+        .unwrap_or(0);
+    let w_backend = 7;
+    let w_expected = checks
+        .iter()
+        .map(|c| c.expected.len())
+        .chain([8])
+        .max()
+        .unwrap_or(0);
+    let w_actual = actual.iter().map(|s| s.len()).chain([6]).max().unwrap_or(0);
+
+    println!(
+        "{header}{:w_name$}  {:w_backend$}  {:w_expected$}  {:w_actual$}  result{header:#}",
+        "check", "backend", "expected", "actual"
+    );
+    for (c, actual) in checks.iter().zip(actual) {
+        let (style, result) = if c.passed {
+            (passed, "ok")
+        } else {
+            (failed, "FAILED")
+        };
+        println!(
+            "{:w_name$}  {:w_backend$}  {:w_expected$}  {actual:w_actual$}  {style}{result}{style:#}",
+            c.name,
+            c.backend.unwrap_or("-"),
+            c.expected,
+        );
+    }
+}
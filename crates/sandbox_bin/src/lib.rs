@@ -1,4 +1,6 @@
 // pub mod config;
+pub mod doctor;
+pub mod serve;
 
 use clap::builder::styling::{AnsiColor, Color, Style};
 
@@ -39,6 +41,44 @@ pub fn get_styles() -> clap::builder::Styles {
         .placeholder(Style::new().fg_color(Some(Color::Ansi(AnsiColor::Cyan))))
 }
 
+/// Print a colored summary of the termination
+pub fn print_termination(term: &sandbox::Termination) {
+    let label = Style::new()
+        .bold()
+        .fg_color(Some(Color::Ansi(AnsiColor::BrightGreen)));
+    let status = Style::new()
+        .bold()
+        .fg_color(Some(Color::Ansi(if term.status.ok() {
+            AnsiColor::Green
+        } else {
+            AnsiColor::Red
+        })));
+    println!(
+        "{label}status{label:#}: {status}{:?}{status:#}",
+        term.status
+    );
+    if let Some(code) = term.exit_code {
+        println!("{label}exit_code{label:#}: {code}");
+    }
+    if let Some(signal) = &term.signal {
+        println!("{label}signal{label:#}: {signal} ({})", signal.number);
+    }
+    println!("{label}real_time{label:#}: {}", term.real_time.pretty());
+    println!("{label}cpu_time{label:#}: {}", term.cpu_time.pretty());
+    if let Some(inst) = term.instructions {
+        println!("{label}instructions{label:#}: {inst}");
+    }
+    println!("{label}memory{label:#}: {}", term.memory.pretty());
+    println!(
+        "{label}context_switches{label:#}: {} voluntary, {} involuntary",
+        term.voluntary_switches, term.involuntary_switches
+    );
+    println!(
+        "{label}page_faults{label:#}: {} minor, {} major",
+        term.minor_page_faults, term.major_page_faults
+    );
+}
+
 use shadow_rs::shadow;
 shadow!(build);
 
@@ -1,26 +1,111 @@
 use std::path::PathBuf;
 
-use clap::{CommandFactory, Parser, Subcommand};
-use sandbox::{unix::SingletonConfig, ExecSandBox};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use sandbox::{
+    unix::{Lim, Limitation, SingletonConfig},
+    Elapse, ExecSandBox, Memory,
+};
+
+fn parse_limits(s: &str) -> Result<Box<Limitation>, sandbox::ParseLimitError> {
+    s.parse().map(Box::new)
+}
+
+/// The command to be executed and its environment.
+#[derive(Args)]
+struct CommandArgs {
+    /// input file (redirected to stdin)
+    #[arg(long)]
+    stdin: Option<String>,
+    /// output file (redirected to stdout)
+    #[arg(long)]
+    stdout: Option<String>,
+    /// error output file (redirected to stderr)
+    #[arg(long)]
+    stderr: Option<String>,
+    /// inhert all env variables of calling process
+    #[arg(long)]
+    set_envs: bool,
+    /// inherit an env variable of calling process (can be repeated)
+    #[arg(long = "inherit-env", value_name = "KEY")]
+    inherit_envs: Vec<String>,
+    /// set an env variable, e. g. `LANG=C` (can be repeated)
+    #[arg(long = "env", value_name = "KEY=VALUE")]
+    envs: Vec<String>,
+    /// run as this user (requires root)
+    #[arg(long, requires = "gid")]
+    uid: Option<u32>,
+    /// run as this group (requires root)
+    #[arg(long, requires = "uid")]
+    gid: Option<u32>,
+    /// supplementary group when running as another user (can be repeated)
+    #[arg(long = "group", value_name = "GID", requires = "uid")]
+    groups: Vec<u32>,
+    /// count the user-space instructions (linux only, requires hardware counters)
+    #[arg(long)]
+    count_instructions: bool,
+    /// run in an empty tmpfs of this size, e. g. `64MiB` (requires root)
+    #[arg(long)]
+    scratch: Option<Memory>,
+    /// resource limits, e. g. `cpu=1s,real=1s,2s,mem=256MiB` (see `sandbox::unix::Limitation`)
+    #[arg(long, value_parser = parse_limits)]
+    limits: Option<Box<Limitation>>,
+
+    /// name of command to be execute
+    cmd: String,
+    /// arguments passed to the command
+    args: Vec<String>,
+}
+
+impl CommandArgs {
+    fn config(self) -> anyhow::Result<SingletonConfig> {
+        let r = std::process::Command::new("which")
+            .arg(&self.cmd)
+            .output()?;
+        let cmd_path = String::from_utf8(r.stdout)?;
+        let cmd_path = cmd_path.trim();
+        if cmd_path.is_empty() {
+            anyhow::bail!("command {:?} not found", self.cmd);
+        }
+        let mut singleton = SingletonConfig::new(cmd_path)
+            .push_args([self.cmd.as_str()])
+            .push_args(self.args.iter().map(|s| s.as_str()));
+        if self.set_envs {
+            singleton = singleton.with_current_env();
+        }
+        singleton = singleton
+            .inherit_envs(self.inherit_envs.iter().map(|s| s.as_str()))
+            .push_envs(self.envs.iter().map(|s| s.as_str()));
+        if let Some(stdin) = self.stdin {
+            singleton = singleton.stdin(stdin);
+        }
+        if let Some(stdout) = self.stdout {
+            singleton = singleton.stdout(stdout);
+        }
+        if let Some(stderr) = self.stderr {
+            singleton = singleton.stderr(stderr);
+        }
+        if let (Some(uid), Some(gid)) = (self.uid, self.gid) {
+            singleton = singleton.user(uid, gid).groups(self.groups);
+        }
+        if self.count_instructions {
+            singleton = singleton.count_instructions();
+        }
+        if let Some(size) = self.scratch {
+            singleton = singleton.scratch(size);
+        }
+        if let Some(limits) = self.limits {
+            singleton = singleton.set_limits(|_| *limits);
+        }
+        Ok(singleton)
+    }
+}
 
 #[derive(Subcommand)]
 enum Commands {
     /// Create a default configuration for a command.
     Show {
-        /// input file (redirected to stdin)
-        #[arg(long)]
-        stdin: Option<String>,
-        /// output file (redirected to stdout)
-        #[arg(long)]
-        stdout: Option<String>,
-        /// inhert the env variables of calling process
-        #[arg(long)]
-        set_envs: bool,
-
-        /// name of command to be execute
-        cmd: String,
-        /// arguments passed to the command
-        args: Vec<String>,
+        #[command(flatten)]
+        command: CommandArgs,
     },
     /// Execute with JSON config file.
     ///
@@ -29,6 +114,57 @@ enum Commands {
         /// path to the JSON config file
         cfg: Option<PathBuf>,
     },
+    /// Execute a command directly and print a summary.
+    ///
+    /// The limits given by flags override those given by `--limits`.
+    Exec {
+        /// time limit, e. g. `1s` (the real time limit is doubled)
+        #[arg(long)]
+        time: Option<Elapse>,
+        /// memory limit, e. g. `256MiB`
+        #[arg(long)]
+        memory: Option<Memory>,
+        /// output file size limit, e. g. `64MiB`
+        #[arg(long)]
+        output: Option<Memory>,
+        /// maximum number of opened files
+        #[arg(long)]
+        fileno: Option<u64>,
+        /// print the JSON output in the same format as `run`
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        command: CommandArgs,
+    },
+    /// Serve requests over a Unix domain socket.
+    ///
+    /// Each request is a JSON config in a single line, and each response is a
+    /// line of JSON in the same format as the output of `run`.
+    Serve {
+        /// path to the socket file
+        #[arg(long)]
+        socket: PathBuf,
+    },
+    /// Check whether the sandbox works as expected on this host.
+    ///
+    /// Canary programs (infinite loop, memory hog, fork bomb, big output) are
+    /// executed in the sandbox, and their statuses are compared with the expected ones.
+    /// It also checks whether programs can be run as an unprivileged user.
+    Doctor {
+        /// also check with the given cgroup v2 directory (see `sandbox::unix::ResourceBackend`)
+        #[arg(long)]
+        cgroup: Option<String>,
+        /// print the checks in JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Canary program used by `doctor`.
+    #[command(hide = true)]
+    Canary {
+        #[arg(value_enum)]
+        kind: zroj_sandbox::doctor::Canary,
+    },
 }
 
 #[derive(Parser)]
@@ -72,30 +208,63 @@ fn main() -> anyhow::Result<()> {
                 .map_err(|e| e.chain().map(|e| e.to_string()).collect::<Vec<String>>());
             serde_json::to_writer_pretty(std::io::stdout(), &term)?;
         }
-        Some(Commands::Show {
-            cmd,
-            args,
-            stdin,
-            stdout,
-            set_envs,
-        }) => {
-            let r = std::process::Command::new("which").arg(&cmd).output()?;
-            let cmd_path = String::from_utf8(r.stdout)?;
-            let cmd_path = cmd_path.trim();
-            let mut singleton = SingletonConfig::new(cmd_path)
-                .push_args([cmd.as_str()])
-                .push_args(args.iter().map(|s| s.as_str()));
-            if set_envs {
-                singleton = singleton.with_current_env();
-            }
-            if let Some(stdin) = stdin {
-                singleton = singleton.stdin(stdin);
+        Some(Commands::Serve { socket }) => {
+            zroj_sandbox::serve::serve(&socket)?;
+        }
+        Some(Commands::Doctor { cgroup, json }) => {
+            let exe = std::env::current_exe()?;
+            let checks = zroj_sandbox::doctor::doctor(&exe, cgroup);
+            if json {
+                serde_json::to_writer_pretty(std::io::stdout(), &checks)?;
+            } else {
+                zroj_sandbox::doctor::print_checks(&checks);
             }
-            if let Some(stdout) = stdout {
-                singleton = singleton.stdout(stdout);
+            if !checks.iter().all(|c| c.passed) {
+                std::process::exit(1);
             }
+        }
+        Some(Commands::Canary { kind }) => {
+            zroj_sandbox::doctor::canary(kind)?;
+        }
+        Some(Commands::Show { command }) => {
+            let singleton = command.config()?;
             serde_json::to_writer_pretty(std::io::stdout(), &singleton)?;
         }
+        Some(Commands::Exec {
+            time,
+            memory,
+            output,
+            fileno,
+            json,
+            command,
+        }) => {
+            let singleton = command.config()?.set_limits(|mut l| {
+                if let Some(time) = time {
+                    l.cpu_time = Lim::Single(time);
+                    l.real_time = Lim::Double(time, time * 2.0);
+                }
+                if let Some(memory) = memory {
+                    l.virtual_memory = Lim::Single(memory);
+                    l.real_memory = Lim::Single(memory);
+                    l.stack_memory = Lim::Single(memory);
+                }
+                if let Some(output) = output {
+                    l.output_memory = Lim::Single(output);
+                }
+                if let Some(fileno) = fileno {
+                    l.fileno = Lim::Single(fileno);
+                }
+                l
+            });
+            let term = singleton.build().exec_sandbox();
+            if json {
+                let term =
+                    term.map_err(|e| e.chain().map(|e| e.to_string()).collect::<Vec<String>>());
+                serde_json::to_writer_pretty(std::io::stdout(), &term)?;
+            } else {
+                zroj_sandbox::print_termination(&term?);
+            }
+        }
         None => {
             cmd.print_help()?;
             return Ok(());
@@ -0,0 +1,67 @@
+//! 常驻的沙盒服务，避免每次执行都启动一个新的 `zroj-sandbox` 进程。
+//!
+//! 客户端通过 Unix domain socket 连接，每个请求和响应各占一行（不含换行符的 JSON）：
+//! 请求是一个 [`SingletonConfig`]，响应可以反序列化为 `Result<sandbox::Termination, Vec<String>>`，
+//! 与 `zroj-sandbox run` 的输出相同。
+//!
+//! 同一个连接上的请求按顺序执行，不同连接上的请求并发执行。
+//! 配置中的相对路径是相对于服务进程的工作目录的，建议使用绝对路径。
+
+use anyhow::Context;
+use sandbox::{unix::SingletonConfig, ExecSandBox, Termination};
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+};
+
+/// 执行一个请求，返回序列化后的响应
+fn handle_request(line: &str) -> serde_json::Result<String> {
+    let term: Result<Termination, Vec<String>> = serde_json::from_str::<SingletonConfig>(line)
+        .map_err(|e| vec![format!("invalid request: {e}")])
+        .and_then(|cfg| {
+            cfg.build()
+                .exec_sandbox()
+                .map_err(|e| e.chain().map(|e| e.to_string()).collect())
+        });
+    serde_json::to_string(&term)
+}
+
+fn handle_connection(stream: UnixStream) -> anyhow::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut resp = handle_request(&line)?;
+        resp.push('\n');
+        writer.write_all(resp.as_bytes())?;
+    }
+}
+
+/// 在 `socket` 上监听请求，直到出错为止。如果 `socket` 已经是一个 socket 文件，会先将其删除。
+pub fn serve(socket: &Path) -> anyhow::Result<()> {
+    if std::fs::symlink_metadata(socket).is_ok_and(|m| m.file_type().is_socket()) {
+        std::fs::remove_file(socket).context("remove stale socket")?;
+    }
+    let listener = UnixListener::bind(socket).context("bind socket")?;
+    eprintln!("[sandbox] listening on {}", socket.display());
+    for stream in listener.incoming() {
+        let stream = stream.context("accept connection")?;
+        std::thread::spawn(move || {
+            if let Err(e) = handle_connection(stream) {
+                eprintln!("[sandbox] connection error: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
@@ -16,8 +16,8 @@ fn main() {
     let dir = tempfile::TempDir::new().unwrap();
     let oneoff = OneOffManager::new(dir.path()).unwrap();
 
-    let source = SourceFile::from_str(SRC, judger::FileType::GnuCpp17O2);
-    let input = StoreFile::from_str(r"1 2", judger::FileType::Plain);
+    let source = SourceFile::from_str(SRC, judger::FileType::GNU_CPP17_O2);
+    let input = StoreFile::from_str(r"1 2", judger::FileType::PLAIN);
 
     let h = std::thread::spawn(move || {
         oneoff.add_test(0, source, input).unwrap();
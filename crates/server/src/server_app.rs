@@ -36,6 +36,10 @@ pub struct ServerAppConfig<A: ToSocketAddrs> {
     listen_address: A,
     gravatar_cdn_base: String, // e.g. "https://sdn.geekzu.org/avatar/"
     frontend_host: String,
+    /// path of the language config, see [`judger::LanguageRegistry`].
+    /// Use the builtin languages if not provided.
+    #[serde(default)]
+    languages: Option<PathBuf>,
 }
 
 impl<A> ServerAppConfig<A>
@@ -89,6 +93,7 @@ pub fn test_server_app_cfg() -> ServerAppConfig<String> {
         listen_address: "127.0.0.1:8080".into(),
         gravatar_cdn_base: "https://sdn.geekzu.org/avatar/".into(),
         frontend_host: "127.0.0.1:3456".into(),
+        languages: None,
     }
 }
 
@@ -146,6 +151,11 @@ impl<A: ToSocketAddrs> ServerApp<A> {
         if self.runtime.is_none() {
             self.prepare_data()?;
         }
+        if let Some(path) = &self.config.languages {
+            judger::LanguageRegistry::load(path)
+                .and_then(|r| r.install())
+                .context("load language config")?;
+        }
         let ServerAppRuntime {
             user_db,
             stmt_db,
@@ -10,7 +10,7 @@ use actix_web::{
     error::{ErrorBadRequest, ErrorInternalServerError},
     web::Json,
 };
-use judger::{StoreFile, TaskReport};
+use judger::{FileType, LanguageRegistry, StoreFile, TaskReport};
 use serde::Serialize;
 use serde_ts_typing::TsType;
 use server_derive::{api, scope_service};
@@ -48,7 +48,7 @@ async fn custom_test_post(
     }
     let input = StoreFile {
         file: payload.input.file.reopen()?,
-        file_type: judger::FileType::Plain,
+        file_type: judger::FileType::PLAIN,
     };
     oneoff
         .add_test(uid, source, input)
@@ -73,9 +73,40 @@ async fn custom_test_get(
     }))
 }
 
+/// 评测端支持的一门语言
+#[derive(Debug, Serialize, TsType)]
+pub struct LanguageInfo {
+    /// 语言标识，作为提交文件名中的 `lang` 部分
+    pub id: FileType,
+    /// 展示给用户的名称
+    pub name: String,
+    /// 源文件的后缀名
+    pub ext: String,
+    /// 是否需要编译
+    pub compileable: bool,
+}
+
+/// List the languages supported by the judger, in the order of the language config.
+#[api(method = get, path = "/languages")]
+async fn languages_get() -> JsonResult<Vec<LanguageInfo>> {
+    Ok(Json(
+        LanguageRegistry::global()
+            .languages()
+            .iter()
+            .map(|lang| LanguageInfo {
+                id: FileType::new(&lang.id),
+                name: lang.name.clone(),
+                ext: lang.ext.clone(),
+                compileable: lang.compile.is_some(),
+            })
+            .collect(),
+    ))
+}
+
 #[scope_service(path = "/custom_test")]
 pub fn service(custom_test_manager: ServerData<OneOffManager>) {
     app_data(custom_test_manager);
     service(custom_test_get);
     service(custom_test_post);
+    service(languages_get);
 }
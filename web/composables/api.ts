@@ -202,38 +202,9 @@ export type Emphasis = {
 */
 export type FileDescriptor = ( "Stdin" | "Stdout" | { Named: string; } );
 /**
- 内置的支持的文件类型
-*/
-export type FileType = (
-    FileTypeAssembly
-    | FileTypeGnuCpp14O2
-    | FileTypeGnuCpp17O2
-    | FileTypeGnuCpp20O2
-    | FileTypePlain
-    | FileTypePython
-    | FileTypeRust
-);
-/**
-*/
-export type FileTypeAssembly = "gnu_assembly";
-/**
-*/
-export type FileTypeGnuCpp14O2 = "gnu_cpp14_o2";
-/**
-*/
-export type FileTypeGnuCpp17O2 = "gnu_cpp17_o2";
-/**
-*/
-export type FileTypeGnuCpp20O2 = "gnu_cpp20_o2";
-/**
-*/
-export type FileTypePlain = "plain";
-/**
+ 文件类型，即评测端语言配置中的语言标识
 */
-export type FileTypePython = "python3";
-/**
-*/
-export type FileTypeRust = "rust";
+export type FileType = string;
 /**
  GFM: footnote definition.
 
@@ -402,8 +373,11 @@ export type JudgeReturn = {
 */
 export type JudgerStatus = (
     JudgerStatusCompileError
+    | JudgerStatusDangerousSyscall
     | JudgerStatusGood
+    | JudgerStatusIdlenessLimitExceeded
     | JudgerStatusMemoryLimitExceeded
+    | JudgerStatusOutputLimitExceeded
     | JudgerStatusRuntimeError
     | JudgerStatusTimeLimitExceeded
 );
@@ -414,6 +388,13 @@ export type JudgerStatusCompileError = {
     name: "compile_error";
     payload: ( undefined | SandboxStatus | null );
 };
+/**
+ 调用了被禁止的系统调用
+*/
+export type JudgerStatusDangerousSyscall = {
+    name: "dangerous_syscall";
+    payload: null;
+};
 /**
  目前没有问题。不等价于通过（得看得分是否等于总分）
 */
@@ -421,6 +402,13 @@ export type JudgerStatusGood = {
     name: "good";
     payload: null;
 };
+/**
+ 程序长时间没有使用 CPU（例如等待读入），被提前终止
+*/
+export type JudgerStatusIdlenessLimitExceeded = {
+    name: "idleness_limit_exceeded";
+    payload: null;
+};
 /**
  超出内存限制
 */
@@ -429,10 +417,18 @@ export type JudgerStatusMemoryLimitExceeded = {
     payload: null;
 };
 /**
+ 输出文件大小超出限制
+*/
+export type JudgerStatusOutputLimitExceeded = {
+    name: "output_limit_exceeded";
+    payload: null;
+};
+/**
+ 运行时错误，附带原因（例如 `SIGSEGV`、`exit code 3`）
 */
 export type JudgerStatusRuntimeError = {
     name: "runtime_error";
-    payload: null;
+    payload: ( undefined | null | string );
 };
 /**
 */
@@ -440,6 +436,15 @@ export type JudgerStatusTimeLimitExceeded = {
     name: "time_limit_exceeded";
     payload: null;
 };
+/**
+ 评测端支持的一门语言
+*/
+export type LanguageInfo = {
+    id: FileType;
+    name: string;
+    ext: string;
+    compileable: boolean;
+};
 /**
  Link.
 
@@ -754,11 +759,25 @@ export type Root = {
  执行的结果状态，只是一个初步的分析，适用于绝大多数情况
 */
 export type SandboxStatus = (
-    SandboxStatusMemoryLimitExceeded
+    SandboxStatusDangerousSyscall
+    | SandboxStatusIdlenessLimitExceeded
+    | SandboxStatusMemoryLimitExceeded
     | SandboxStatusOk
+    | SandboxStatusOutputLimitExceeded
+    | SandboxStatusProcessLimitExceeded
     | SandboxStatusRuntimeError
     | SandboxStatusTimeLimitExceeded
 );
+/**
+ 调用了被禁止的系统调用（附带系统调用号）
+*/
+export type SandboxStatusDangerousSyscall = {
+    DangerousSyscall: number;
+};
+/**
+ CPU 时间长时间没有增长（例如阻塞在读入或者休眠），被提前终止
+*/
+export type SandboxStatusIdlenessLimitExceeded = "IdlenessLimitExceeded";
 /**
  超出内存限制
 */
@@ -767,6 +786,16 @@ export type SandboxStatusMemoryLimitExceeded = "MemoryLimitExceeded";
  All Correct
 */
 export type SandboxStatusOk = "Ok";
+/**
+ 输出文件大小超出限制
+*/
+export type SandboxStatusOutputLimitExceeded = "OutputLimitExceeded";
+/**
+ 因创建的进程（线程）数超出限制而运行错误（附带状态码）
+*/
+export type SandboxStatusProcessLimitExceeded = {
+    ProcessLimitExceeded: number;
+};
 /**
  with status code
 */
@@ -1088,6 +1117,16 @@ export function useAPI () {
                 fetch: (payload: CustomTestPostPayload | Ref<CustomTestPostPayload>) => fetchAPI("post", "/custom_test", payload) as Promise<CustomTestPostReturn>,
                 key: "/custom_test:post",
             },
+            languages: {
+                /**
+                 List the languages supported by the judger, in the order of the language config.
+                 */
+                get: { 
+                    use: () => callAPI("get", "/custom_test/languages") as Promise<ExtAsyncData<CustomTestLanguagesGetReturn | null>>,
+                    fetch: () => fetchAPI("get", "/custom_test/languages") as Promise<CustomTestLanguagesGetReturn>,
+                    key: "/custom_test/languages:get",
+                },
+            },
         },
         problem: {
             metas: {
@@ -1305,6 +1344,7 @@ export type ContestRegistrantsGetReturn = UserMeta[];
 export type ContestRegistrantsPostPayload = CtstRegistInfo;
 export type ContestRegistrantsPostReturn = any;
 export type CustomTestGetReturn = CustomTestResult;
+export type CustomTestLanguagesGetReturn = LanguageInfo[];
 export type CustomTestPostPayload = FormData;
 export type CustomTestPostReturn = any;
 export type ProblemFulldataMetaGetPayload = FullDataMetaQuery;
@@ -3,6 +3,7 @@ export const statusTitle = {
   compile_error: "Compile Error",
   custom: "Unknown Error",
   dangerous_syscall: "Dangerous System Call",
+  idleness_limit_exceeded: "Idleness Limit Exceeded",
   memory_limit_exceeded: "Memory Limit Exceeded",
   output_limit_exceeded: "Output Limit Exceeded",
   partial: "Partially Accepted",